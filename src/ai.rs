@@ -1,42 +1,278 @@
 use crate::config::UserConfig;
+use crate::error::WtfError;
+use crate::logging;
+use crate::symbols;
+use crate::ui::{decor, out};
 use colored::Colorize;
 use std::env;
 
-pub fn check_api_key() -> Result<String, String> {
+pub fn check_api_key(config: &UserConfig) -> Result<String, WtfError> {
+  api_keys(config)?
+    .into_iter()
+    .next()
+    .ok_or(WtfError::ApiKeyMissing)
+}
+
+// GOOGLE_API_KEY first, then the configured keys, in the order they're tried.
+fn api_keys(config: &UserConfig) -> Result<Vec<String>, WtfError> {
+  let mut keys = Vec::new();
+
   if let Ok(key) = env::var("GOOGLE_API_KEY") {
     if !key.is_empty() {
-      return Ok(key);
+      keys.push(key);
     }
   }
 
-  let config = UserConfig::load();
-  if let Some(key) = config.get_google_api_key() {
-    if !key.is_empty() {
-      return Ok(key);
+  for key in config.get_google_api_keys() {
+    if !keys.contains(&key) {
+      keys.push(key);
     }
   }
 
-  Err("Google API key not found".to_string())
+  if keys.is_empty() {
+    return Err(WtfError::ApiKeyMissing);
+  }
+
+  Ok(keys)
 }
 
-pub fn save_api_key(key: String) -> Result<(), String> {
-  let mut config = UserConfig::load();
-  config.set_google_api_key(key);
-  config.save()?;
-  Ok(())
+pub fn save_api_keys(config: &mut UserConfig, keys: Vec<String>) -> Result<(), WtfError> {
+  config.set_google_api_keys(keys);
+  config.save()
 }
 
-pub async fn fix_command_with_ai(wrong_command: &str) -> Result<String, String> {
-  use reqwest::Client;
-  use serde_json::json;
+fn truncate_for_prompt(command: &str, max_chars: usize) -> String {
+  if command.chars().count() <= max_chars {
+    return command.to_string();
+  }
+
+  out!(
+    "{}",
+    format!(
+      "{} Command is longer than {} characters, truncating it for the AI prompt.",
+      symbols::warn(),
+      max_chars
+    )
+    .yellow()
+  );
 
-  let api_key = check_api_key()?;
+  let truncated: String = command.chars().take(max_chars).collect();
+  format!("{}{}", truncated, symbols::ellipsis())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AiProvider {
+  Gemini,
+  Ollama,
+}
+
+impl AiProvider {
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name.to_lowercase().as_str() {
+      "gemini" | "google" => Some(AiProvider::Gemini),
+      "ollama" => Some(AiProvider::Ollama),
+      _ => None,
+    }
+  }
+
+  pub fn display_name(&self) -> &'static str {
+    match self {
+      AiProvider::Gemini => "Google Gemini",
+      AiProvider::Ollama => "Ollama",
+    }
+  }
+}
+
+pub struct AiFix {
+  pub command: String,
+  pub provider: AiProvider,
+  /// Which of the Gemini API keys answered (0-based), None for other providers
+  pub key_index: Option<usize>,
+  /// The model's one-line reason for the fix, when asked for with `--explain-ai`
+  pub rationale: Option<String>,
+  /// Other distinct candidates in the order Gemini ranked them (`ai_candidate_count`)
+  pub alternatives: Vec<AiAlternative>,
+}
 
-  println!(
+pub struct AiAlternative {
+  pub command: String,
+  pub rationale: Option<String>,
+}
+
+/// Gemini's upper limit for `candidateCount`
+pub const MAX_AI_CANDIDATES: usize = 8;
+
+/// A suggestion the user turned down at the prompt, and what they said was wrong with it.
+pub struct Refinement {
+  pub suggestion: String,
+  pub hint: String,
+}
+
+const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+const DEFAULT_OLLAMA_MODEL: &str = "llama3.2";
+
+fn build_prompt(wrong_command: &str, explain: bool, refinements: &[Refinement]) -> String {
+  let mut prompt = if explain {
+    format!(
+      "You are a shell command expert. Fix this command. Answer with exactly two lines and nothing else:\n\
+       COMMAND: <the corrected command>\n\
+       WHY: <one short sentence on what was wrong>\n\n\
+       The command: {}",
+      wrong_command
+    )
+  } else {
+    format!(
+      "You are a shell command expert. Fix this command and output ONLY the corrected command, nothing else: {}",
+      wrong_command
+    )
+  };
+
+  for refinement in refinements {
+    prompt.push_str(&format!(
+      "\n\nYou suggested `{}`, but that is not what the user wants. They said: {}",
+      refinement.suggestion, refinement.hint
+    ));
+  }
+
+  prompt
+}
+
+/// With `explain`, the model is also asked why, and `AiFix::rationale` holds its answer.
+/// `refinements` are earlier suggestions from this run the user asked to have redone.
+pub async fn fix_command_with_ai(
+  wrong_command: &str,
+  config: &UserConfig,
+  explain: bool,
+  refinements: &[Refinement],
+) -> Result<AiFix, WtfError> {
+  let wrong_command = truncate_for_prompt(wrong_command, config.ai_max_input_chars);
+  let prompt = build_prompt(&wrong_command, explain, refinements);
+
+  let primary_error = match fix_with_provider(AiProvider::Gemini, &prompt, config, explain).await {
+    Ok(fix) => return Ok(fix),
+    Err(e) => e,
+  };
+
+  let fallback = match &config.ai_fallback_provider {
+    Some(name) => AiProvider::from_name(name)
+      .ok_or_else(|| WtfError::ConfigError(format!("Unknown AI fallback provider: {}", name)))?,
+    None => return Err(primary_error),
+  };
+
+  if fallback == AiProvider::Gemini {
+    return Err(primary_error);
+  }
+
+  out!(
+    "{}",
+    format!(
+      "{} {} failed ({}), trying {}...",
+      symbols::warn(),
+      AiProvider::Gemini.display_name(),
+      primary_error,
+      fallback.display_name()
+    )
+    .yellow()
+  );
+
+  fix_with_provider(fallback, &prompt, config, explain)
+    .await
+    .map_err(|e| {
+      WtfError::AiRequestFailed(format!(
+        "{}; {} also failed: {}",
+        primary_error,
+        fallback.display_name(),
+        e
+      ))
+    })
+}
+
+async fn fix_with_provider(
+  provider: AiProvider,
+  prompt: &str,
+  config: &UserConfig,
+  explain: bool,
+) -> Result<AiFix, WtfError> {
+  decor!(
     "{}",
-    "🤖 Asking Google Gemini to fix the command...".bright_cyan()
+    format!(
+      "{} Asking {} to fix the command...",
+      symbols::ai(),
+      provider.display_name()
+    )
+    .bright_cyan()
   );
 
+  let asked = match provider {
+    AiProvider::Gemini => ask_gemini(prompt, config)
+      .await
+      .map(|(responses, key_index)| (responses, Some(key_index))),
+    AiProvider::Ollama => ask_ollama(prompt, config)
+      .await
+      .map(|response| (vec![response], None)),
+  };
+
+  let (responses, key_index) = match asked {
+    Ok(answer) => answer,
+    Err(e) => {
+      logging::log(&format!("{} failed: {}", provider.display_name(), e));
+      return Err(e);
+    }
+  };
+
+  let mut parsed = Vec::new();
+  let mut first_error = None;
+  for response in &responses {
+    logging::log(&format!(
+      "{} answered: {}",
+      provider.display_name(),
+      response
+    ));
+
+    let result = if explain {
+      parse_explained_response(response)
+    } else {
+      clean_ai_response(response).map(|command| (command, None))
+    };
+    match result {
+      Ok((command, rationale)) => {
+        if !parsed.iter().any(|(seen, _)| *seen == command) {
+          parsed.push((command, rationale));
+        }
+      }
+      Err(e) => {
+        first_error.get_or_insert(e);
+      }
+    }
+  }
+
+  let mut parsed = parsed.into_iter();
+  let Some((command, rationale)) = parsed.next() else {
+    return Err(
+      first_error.unwrap_or_else(|| WtfError::AiRequestFailed("No response from AI".to_string())),
+    );
+  };
+
+  Ok(AiFix {
+    command,
+    provider,
+    key_index,
+    rationale,
+    alternatives: parsed
+      .map(|(command, rationale)| AiAlternative { command, rationale })
+      .collect(),
+  })
+}
+
+// Returns the answers (one per candidate) and the index of the key that got them. A rate-limited key
+// (HTTP 429) moves on to the next one. When the last key is rate limited, a short
+// enough retry delay is waited out once; otherwise the delay is reported.
+async fn ask_gemini(prompt: &str, config: &UserConfig) -> Result<(Vec<String>, usize), WtfError> {
+  use reqwest::{Client, StatusCode};
+  use serde_json::json;
+
+  let api_keys = api_keys(config)?;
+
   let client = Client::new();
   let url =
     "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.0-flash:generateContent";
@@ -44,46 +280,179 @@ pub async fn fix_command_with_ai(wrong_command: &str) -> Result<String, String>
   let payload = json!({
       "contents": [{
           "parts": [{
-              "text": format!(
-                  "You are a shell command expert. Fix this command and output ONLY the corrected command, nothing else: {}",
-                  wrong_command
-              )
+              "text": prompt
           }]
       }],
       "generationConfig": {
           "temperature": 0.1,
           "maxOutputTokens": 100,
+          "candidateCount": config.ai_candidate_count.clamp(1, MAX_AI_CANDIDATES),
+      }
+  });
+
+  let mut index = 0;
+  let mut retried = false;
+
+  while let Some(api_key) = api_keys.get(index) {
+    let response = client
+      .post(url)
+      .header("Content-Type", "application/json")
+      .header("X-goog-api-key", api_key)
+      .json(&payload)
+      .send()
+      .await
+      .map_err(|e| WtfError::AiRequestFailed(format!("API request failed: {}", e)))?;
+
+    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+      let retry_after = retry_after_secs(response).await;
+      let is_last = index + 1 == api_keys.len();
+
+      if !is_last {
+        decor!(
+          "{}",
+          format!(
+            "{} API key #{} is rate limited, trying the next one...",
+            symbols::warn(),
+            index + 1
+          )
+          .yellow()
+        );
+        index += 1;
+        continue;
+      }
+
+      match retry_after {
+        Some(secs) if !retried && secs <= config.ai_retry_wait_secs => {
+          decor!(
+            "{}",
+            format!(
+              "{} Rate limited by {}, retrying in {}s...",
+              symbols::warn(),
+              AiProvider::Gemini.display_name(),
+              secs
+            )
+            .yellow()
+          );
+          tokio::time::sleep(std::time::Duration::from_secs(secs)).await;
+          retried = true;
+          continue;
+        }
+        Some(secs) => {
+          return Err(WtfError::AiRequestFailed(format!(
+            "rate limited, retry in {}s",
+            secs
+          )));
+        }
+        None => {
+          return Err(WtfError::AiRequestFailed(
+            "rate limited, try again later".to_string(),
+          ));
+        }
+      }
+    }
+
+    if !response.status().is_success() {
+      return Err(WtfError::AiRequestFailed(format!(
+        "API returned error: {}",
+        response.status()
+      )));
+    }
+
+    let result: serde_json::Value = response
+      .json()
+      .await
+      .map_err(|e| WtfError::AiRequestFailed(format!("Failed to parse response: {}", e)))?;
+
+    let texts: Vec<String> = result["candidates"]
+      .as_array()
+      .into_iter()
+      .flatten()
+      .filter_map(|candidate| candidate["content"]["parts"][0]["text"].as_str())
+      .map(|text| text.trim().to_string())
+      .collect();
+    if texts.is_empty() {
+      return Err(WtfError::AiRequestFailed("No response from AI".to_string()));
+    }
+
+    return Ok((texts, index));
+  }
+
+  Err(WtfError::ApiKeyMissing)
+}
+
+// Seconds to wait from the `Retry-After` header, or from the `RetryInfo` detail
+// in Gemini's error body (`"retryDelay": "37s"`), rounded up.
+async fn retry_after_secs(response: reqwest::Response) -> Option<u64> {
+  let header = response
+    .headers()
+    .get(reqwest::header::RETRY_AFTER)
+    .and_then(|value| value.to_str().ok())
+    .and_then(|value| value.trim().parse::<u64>().ok());
+  if header.is_some() {
+    return header;
+  }
+
+  let body: serde_json::Value = response.json().await.ok()?;
+  body["error"]["details"]
+    .as_array()?
+    .iter()
+    .filter_map(|detail| detail["retryDelay"].as_str())
+    .find_map(|delay| delay.strip_suffix('s')?.parse::<f64>().ok())
+    .map(|secs| secs.max(0.0).ceil() as u64)
+}
+
+async fn ask_ollama(prompt: &str, config: &UserConfig) -> Result<String, WtfError> {
+  use reqwest::Client;
+  use serde_json::json;
+
+  let base_url = config
+    .ollama_url
+    .as_deref()
+    .unwrap_or(DEFAULT_OLLAMA_URL)
+    .trim_end_matches('/');
+  let model = config
+    .ollama_model
+    .as_deref()
+    .unwrap_or(DEFAULT_OLLAMA_MODEL);
+
+  let payload = json!({
+      "model": model,
+      "prompt": prompt,
+      "stream": false,
+      "options": {
+          "temperature": 0.1,
       }
   });
 
-  let response = client
-    .post(url)
-    .header("Content-Type", "application/json")
-    .header("X-goog-api-key", api_key)
+  let response = Client::new()
+    .post(format!("{}/api/generate", base_url))
     .json(&payload)
     .send()
     .await
-    .map_err(|e| format!("API request failed: {}", e))?;
+    .map_err(|e| WtfError::AiRequestFailed(format!("API request failed: {}", e)))?;
 
   if !response.status().is_success() {
-    return Err(format!("API returned error: {}", response.status()));
+    return Err(WtfError::AiRequestFailed(format!(
+      "API returned error: {}",
+      response.status()
+    )));
   }
 
   let result: serde_json::Value = response
     .json()
     .await
-    .map_err(|e| format!("Failed to parse response: {}", e))?;
+    .map_err(|e| WtfError::AiRequestFailed(format!("Failed to parse response: {}", e)))?;
 
-  let fixed_command = result["candidates"][0]["content"]["parts"][0]["text"]
+  let text = result["response"]
     .as_str()
-    .ok_or("No response from AI")?
+    .ok_or_else(|| WtfError::AiRequestFailed("No response from AI".to_string()))?
     .trim()
     .to_string();
 
-  clean_ai_response(&fixed_command)
+  Ok(text)
 }
 
-fn clean_ai_response(response: &str) -> Result<String, String> {
+fn clean_ai_response(response: &str) -> Result<String, WtfError> {
   let cleaned = response
     .trim_matches('`')
     .trim_matches('"')
@@ -95,34 +464,65 @@ fn clean_ai_response(response: &str) -> Result<String, String> {
     .to_string();
 
   if cleaned.is_empty() {
-    return Err("AI returned empty response".to_string());
+    return Err(WtfError::AiRequestFailed(
+      "AI returned empty response".to_string(),
+    ));
   }
 
   Ok(cleaned)
 }
 
+// Splits a `COMMAND: ...` / `WHY: ...` answer. Models dress the labels up in markdown
+// or ignore the format now and then, so without a COMMAND line the whole answer is
+// read as a plain one and there is no rationale.
+fn parse_explained_response(response: &str) -> Result<(String, Option<String>), WtfError> {
+  let field = |label: &str| {
+    response.lines().find_map(|line| {
+      let line = line.trim().trim_start_matches(['*', '-', '#', ' ']);
+      let (name, value) = line.split_once(':')?;
+      let name = name.trim_end_matches('*').trim();
+      name
+        .eq_ignore_ascii_case(label)
+        .then(|| value.trim().trim_start_matches('*').trim().to_string())
+        .filter(|value| !value.is_empty())
+    })
+  };
+
+  let Some(command) = field("COMMAND") else {
+    return Ok((clean_ai_response(response)?, None));
+  };
+  Ok((clean_ai_response(&command)?, field("WHY")))
+}
+
 pub fn display_api_key_help() {
-  println!("{}", "❌ Google API key not found!".bright_red());
-  println!();
-  println!(
+  out!(
+    "{}",
+    format!("{} Google API key not found!", symbols::error()).bright_red()
+  );
+  out!();
+  out!(
     "{}",
     "To use AI-powered fixing, you need a Google AI API key:".bright_yellow()
   );
-  println!();
-  println!("{}", "Option 1: Set environment variable".bright_cyan());
-  println!("  Windows PowerShell:");
-  println!("    $env:GOOGLE_API_KEY = \"your-key-here\"");
-  println!("  Linux/macOS:");
-  println!("    export GOOGLE_API_KEY=\"your-key-here\"");
-  println!();
-  println!("{}", "Option 2: Save to config".bright_cyan());
-  println!("  wtf set-api-key your-key-here");
-  println!();
-  println!("{}", "Get your API key from:".bright_cyan());
-  println!("  https://aistudio.google.com/app/apikey");
-  println!();
-  println!(
+  out!();
+  out!("{}", "Option 1: Set environment variable".bright_cyan());
+  out!("  Windows PowerShell:");
+  out!("    $env:GOOGLE_API_KEY = \"your-key-here\"");
+  out!("  Linux/macOS:");
+  out!("    export GOOGLE_API_KEY=\"your-key-here\"");
+  out!();
+  out!("{}", "Option 2: Save to config".bright_cyan());
+  out!("  wtf set-api-key your-key-here");
+  out!();
+  out!("{}", "Get your API key from:".bright_cyan());
+  out!("  https://aistudio.google.com/app/apikey");
+  out!();
+  out!(
     "{}",
-    "💡 Tip: AI mode uses Google Gemini 2.0 Flash model".dimmed()
+    format!(
+      "{} Tip: AI mode uses Google Gemini 2.0 Flash model",
+      symbols::tip()
+    )
+    .dimmed()
   );
 }
@@ -1,352 +1,356 @@
 // Massive database of common command typos
 
-pub fn get_common_fixes() -> Vec<(&'static str, (&'static str, &'static str))> {
+/// `(typo, (fix, reason, confidence))`. A `None` confidence means 1.0; give
+/// speculative fixes (e.g. a typo that is also a real program) a lower one.
+pub type BuiltinFix = (&'static str, (&'static str, &'static str, Option<f64>));
+
+pub fn get_common_fixes() -> Vec<BuiltinFix> {
   vec![
     // ==================== NPM ====================
-    ("npm onstall", ("npm install", "npm typo")),
-    ("npm isntall", ("npm install", "npm typo")),
-    ("npm intall", ("npm install", "npm typo")),
-    ("npm instal", ("npm install", "npm typo")),
-    ("npm instlal", ("npm install", "npm typo")),
-    ("npm insatll", ("npm install", "npm typo")),
-    ("npm nistall", ("npm install", "npm typo")),
-    ("npm unistall", ("npm uninstall", "npm typo")),
-    ("npm unintsall", ("npm uninstall", "npm typo")),
-    ("npm upate", ("npm update", "npm typo")),
-    ("npm udpate", ("npm update", "npm typo")),
-    ("npm statr", ("npm start", "npm typo")),
-    ("npm strat", ("npm start", "npm typo")),
-    ("npm satrt", ("npm start", "npm typo")),
-    ("npm tets", ("npm test", "npm typo")),
-    ("npm tset", ("npm test", "npm typo")),
-    ("npm rnu", ("npm run", "npm typo")),
-    ("npm biuld", ("npm build", "npm typo")),
-    ("npm bulid", ("npm build", "npm typo")),
-    ("npm inint", ("npm init", "npm typo")),
-    ("npm pubilsh", ("npm publish", "npm typo")),
-    ("npm publsih", ("npm publish", "npm typo")),
-    ("npm serach", ("npm search", "npm typo")),
-    ("npm searhc", ("npm search", "npm typo")),
-    ("onstall", ("install", "npm shorthand")),
-    ("isntall", ("install", "npm shorthand")),
-    ("intall", ("install", "npm shorthand")),
+    ("npm onstall", ("npm install", "npm typo", None)),
+    ("npm isntall", ("npm install", "npm typo", None)),
+    ("npm intall", ("npm install", "npm typo", None)),
+    ("npm instal", ("npm install", "npm typo", None)),
+    ("npm instlal", ("npm install", "npm typo", None)),
+    ("npm insatll", ("npm install", "npm typo", None)),
+    ("npm nistall", ("npm install", "npm typo", None)),
+    ("npm unistall", ("npm uninstall", "npm typo", None)),
+    ("npm unintsall", ("npm uninstall", "npm typo", None)),
+    ("npm upate", ("npm update", "npm typo", None)),
+    ("npm udpate", ("npm update", "npm typo", None)),
+    ("npm statr", ("npm start", "npm typo", None)),
+    ("npm strat", ("npm start", "npm typo", None)),
+    ("npm satrt", ("npm start", "npm typo", None)),
+    ("npm tets", ("npm test", "npm typo", None)),
+    ("npm tset", ("npm test", "npm typo", None)),
+    ("npm rnu", ("npm run", "npm typo", None)),
+    ("npm biuld", ("npm build", "npm typo", None)),
+    ("npm bulid", ("npm build", "npm typo", None)),
+    ("npm inint", ("npm init", "npm typo", None)),
+    ("npm pubilsh", ("npm publish", "npm typo", None)),
+    ("npm publsih", ("npm publish", "npm typo", None)),
+    ("npm serach", ("npm search", "npm typo", None)),
+    ("npm searhc", ("npm search", "npm typo", None)),
+    ("onstall", ("install", "npm shorthand", None)),
+    ("isntall", ("install", "npm shorthand", None)),
+    ("intall", ("install", "npm shorthand", None)),
     // ==================== YARN ====================
-    ("yarn isntall", ("yarn install", "yarn typo")),
-    ("yarn intall", ("yarn install", "yarn typo")),
-    ("yarn onstall", ("yarn install", "yarn typo")),
-    ("yarn statr", ("yarn start", "yarn typo")),
-    ("yarn tset", ("yarn test", "yarn typo")),
-    ("yarn biuld", ("yarn build", "yarn typo")),
-    ("yarn bulid", ("yarn build", "yarn typo")),
+    ("yarn isntall", ("yarn install", "yarn typo", None)),
+    ("yarn intall", ("yarn install", "yarn typo", None)),
+    ("yarn onstall", ("yarn install", "yarn typo", None)),
+    ("yarn statr", ("yarn start", "yarn typo", None)),
+    ("yarn tset", ("yarn test", "yarn typo", None)),
+    ("yarn biuld", ("yarn build", "yarn typo", None)),
+    ("yarn bulid", ("yarn build", "yarn typo", None)),
     // ==================== PNPM ====================
-    ("pnpm isntall", ("pnpm install", "pnpm typo")),
-    ("pnpm intall", ("pnpm install", "pnpm typo")),
-    ("pnpm statr", ("pnpm start", "pnpm typo")),
+    ("pnpm isntall", ("pnpm install", "pnpm typo", None)),
+    ("pnpm intall", ("pnpm install", "pnpm typo", None)),
+    ("pnpm statr", ("pnpm start", "pnpm typo", None)),
     // ==================== GIT ====================
-    ("git comit", ("git commit", "git typo")),
-    ("git commti", ("git commit", "git typo")),
-    ("git commmit", ("git commit", "git typo")),
-    ("git commit", ("git commit", "git typo")),
-    ("git comimt", ("git commit", "git typo")),
-    ("git pussh", ("git push", "git typo")),
-    ("git puhs", ("git push", "git typo")),
-    ("git psuh", ("git push", "git typo")),
-    ("git pus", ("git push", "git typo")),
-    ("git statsu", ("git status", "git typo")),
-    ("git stauts", ("git status", "git typo")),
-    ("git status", ("git status", "git typo")),
-    ("git sttaus", ("git status", "git typo")),
-    ("git staus", ("git status", "git typo")),
-    ("git checkotu", ("git checkout", "git typo")),
-    ("git checout", ("git checkout", "git typo")),
-    ("git chekout", ("git checkout", "git typo")),
-    ("git chekcout", ("git checkout", "git typo")),
-    ("git branh", ("git branch", "git typo")),
-    ("git branhc", ("git branch", "git typo")),
-    ("git branc", ("git branch", "git typo")),
-    ("git brnach", ("git branch", "git typo")),
-    ("git meger", ("git merge", "git typo")),
-    ("git megre", ("git merge", "git typo")),
-    ("git mrege", ("git merge", "git typo")),
-    ("git pull", ("git pull", "git typo")),
-    ("git plul", ("git pull", "git typo")),
-    ("git puull", ("git pull", "git typo")),
-    ("git clon", ("git clone", "git typo")),
-    ("git clone", ("git clone", "git typo")),
-    ("git cloen", ("git clone", "git typo")),
-    ("git fetc", ("git fetch", "git typo")),
-    ("git fethc", ("git fetch", "git typo")),
-    ("git ad", ("git add", "git typo")),
-    ("git add", ("git add", "git typo")),
-    ("git dif", ("git diff", "git typo")),
-    ("git diff", ("git diff", "git typo")),
-    ("git dfif", ("git diff", "git typo")),
-    ("git log", ("git log", "git typo")),
-    ("git lgo", ("git log", "git typo")),
-    ("git inti", ("git init", "git typo")),
-    ("git initt", ("git init", "git typo")),
-    ("git reset", ("git reset", "git typo")),
-    ("git rset", ("git reset", "git typo")),
-    ("git reabse", ("git rebase", "git typo")),
-    ("git rebsae", ("git rebase", "git typo")),
-    ("git stsh", ("git stash", "git typo")),
-    ("git satsh", ("git stash", "git typo")),
-    ("git rmeote", ("git remote", "git typo")),
-    ("git remtoe", ("git remote", "git typo")),
-    ("git tag", ("git tag", "git typo")),
-    ("git tga", ("git tag", "git typo")),
-    ("git shwo", ("git show", "git typo")),
-    ("git sohw", ("git show", "git typo")),
-    ("git blame", ("git blame", "git typo")),
-    ("git balme", ("git blame", "git typo")),
+    ("git comit", ("git commit", "git typo", None)),
+    ("git commti", ("git commit", "git typo", None)),
+    ("git commmit", ("git commit", "git typo", None)),
+    ("git commit", ("git commit", "git typo", None)),
+    ("git comimt", ("git commit", "git typo", None)),
+    ("git pussh", ("git push", "git typo", None)),
+    ("git puhs", ("git push", "git typo", None)),
+    ("git psuh", ("git push", "git typo", None)),
+    ("git pus", ("git push", "git typo", None)),
+    ("git statsu", ("git status", "git typo", None)),
+    ("git stauts", ("git status", "git typo", None)),
+    ("git status", ("git status", "git typo", None)),
+    ("git sttaus", ("git status", "git typo", None)),
+    ("git staus", ("git status", "git typo", None)),
+    ("git checkotu", ("git checkout", "git typo", None)),
+    ("git checout", ("git checkout", "git typo", None)),
+    ("git chekout", ("git checkout", "git typo", None)),
+    ("git chekcout", ("git checkout", "git typo", None)),
+    ("git branh", ("git branch", "git typo", None)),
+    ("git branhc", ("git branch", "git typo", None)),
+    ("git branc", ("git branch", "git typo", None)),
+    ("git brnach", ("git branch", "git typo", None)),
+    ("git meger", ("git merge", "git typo", None)),
+    ("git megre", ("git merge", "git typo", None)),
+    ("git mrege", ("git merge", "git typo", None)),
+    ("git pull", ("git pull", "git typo", None)),
+    ("git plul", ("git pull", "git typo", None)),
+    ("git puull", ("git pull", "git typo", None)),
+    ("git clon", ("git clone", "git typo", None)),
+    ("git clone", ("git clone", "git typo", None)),
+    ("git cloen", ("git clone", "git typo", None)),
+    ("git fetc", ("git fetch", "git typo", None)),
+    ("git fethc", ("git fetch", "git typo", None)),
+    ("git ad", ("git add", "git typo", None)),
+    ("git add", ("git add", "git typo", None)),
+    ("git dif", ("git diff", "git typo", None)),
+    ("git diff", ("git diff", "git typo", None)),
+    ("git dfif", ("git diff", "git typo", None)),
+    ("git log", ("git log", "git typo", None)),
+    ("git lgo", ("git log", "git typo", None)),
+    ("git inti", ("git init", "git typo", None)),
+    ("git initt", ("git init", "git typo", None)),
+    ("git reset", ("git reset", "git typo", None)),
+    ("git rset", ("git reset", "git typo", None)),
+    ("git reabse", ("git rebase", "git typo", None)),
+    ("git rebsae", ("git rebase", "git typo", None)),
+    ("git stsh", ("git stash", "git typo", None)),
+    ("git satsh", ("git stash", "git typo", None)),
+    ("git rmeote", ("git remote", "git typo", None)),
+    ("git remtoe", ("git remote", "git typo", None)),
+    ("git tag", ("git tag", "git typo", None)),
+    ("git tga", ("git tag", "git typo", None)),
+    ("git shwo", ("git show", "git typo", None)),
+    ("git sohw", ("git show", "git typo", None)),
+    ("git blame", ("git blame", "git typo", None)),
+    ("git balme", ("git blame", "git typo", None)),
     // ==================== CARGO ====================
-    ("cargo biuld", ("cargo build", "cargo typo")),
-    ("cargo bulid", ("cargo build", "cargo typo")),
-    ("cargo buld", ("cargo build", "cargo typo")),
-    ("cargo buidl", ("cargo build", "cargo typo")),
-    ("cargo rnu", ("cargo run", "cargo typo")),
-    ("cargo rnu", ("cargo run", "cargo typo")),
-    ("cargo tset", ("cargo test", "cargo typo")),
-    ("cargo tets", ("cargo test", "cargo typo")),
-    ("cargo chekc", ("cargo check", "cargo typo")),
-    ("cargo check", ("cargo check", "cargo typo")),
-    ("cargo cehck", ("cargo check", "cargo typo")),
-    ("cargo clen", ("cargo clean", "cargo typo")),
-    ("cargo claen", ("cargo clean", "cargo typo")),
-    ("cargo doc", ("cargo doc", "cargo typo")),
-    ("cargo dco", ("cargo doc", "cargo typo")),
-    ("cargo fmt", ("cargo fmt", "cargo typo")),
-    ("cargo fmtt", ("cargo fmt", "cargo typo")),
-    ("cargo cilppy", ("cargo clippy", "cargo typo")),
-    ("cargo clppy", ("cargo clippy", "cargo typo")),
-    ("cargo clipy", ("cargo clippy", "cargo typo")),
-    ("cargo pubilsh", ("cargo publish", "cargo typo")),
-    ("cargo publsih", ("cargo publish", "cargo typo")),
-    ("cargo serach", ("cargo search", "cargo typo")),
-    ("cargo searhc", ("cargo search", "cargo typo")),
-    ("cargo benchmakr", ("cargo bench", "cargo typo")),
-    ("cargo updaet", ("cargo update", "cargo typo")),
-    ("cargo udpate", ("cargo update", "cargo typo")),
+    ("cargo biuld", ("cargo build", "cargo typo", None)),
+    ("cargo bulid", ("cargo build", "cargo typo", None)),
+    ("cargo buld", ("cargo build", "cargo typo", None)),
+    ("cargo buidl", ("cargo build", "cargo typo", None)),
+    ("cargo rnu", ("cargo run", "cargo typo", None)),
+    ("cargo rnu", ("cargo run", "cargo typo", None)),
+    ("cargo tset", ("cargo test", "cargo typo", None)),
+    ("cargo tets", ("cargo test", "cargo typo", None)),
+    ("cargo chekc", ("cargo check", "cargo typo", None)),
+    ("cargo check", ("cargo check", "cargo typo", None)),
+    ("cargo cehck", ("cargo check", "cargo typo", None)),
+    ("cargo clen", ("cargo clean", "cargo typo", None)),
+    ("cargo claen", ("cargo clean", "cargo typo", None)),
+    ("cargo doc", ("cargo doc", "cargo typo", None)),
+    ("cargo dco", ("cargo doc", "cargo typo", None)),
+    ("cargo fmt", ("cargo fmt", "cargo typo", None)),
+    ("cargo fmtt", ("cargo fmt", "cargo typo", None)),
+    ("cargo cilppy", ("cargo clippy", "cargo typo", None)),
+    ("cargo clppy", ("cargo clippy", "cargo typo", None)),
+    ("cargo clipy", ("cargo clippy", "cargo typo", None)),
+    ("cargo pubilsh", ("cargo publish", "cargo typo", None)),
+    ("cargo publsih", ("cargo publish", "cargo typo", None)),
+    ("cargo serach", ("cargo search", "cargo typo", None)),
+    ("cargo searhc", ("cargo search", "cargo typo", None)),
+    ("cargo benchmakr", ("cargo bench", "cargo typo", None)),
+    ("cargo updaet", ("cargo update", "cargo typo", None)),
+    ("cargo udpate", ("cargo update", "cargo typo", None)),
     // ==================== DOCKER ====================
-    ("dokcer", ("docker", "docker typo")),
-    ("doker", ("docker", "docker typo")),
-    ("dockre", ("docker", "docker typo")),
-    ("docker rnu", ("docker run", "docker typo")),
-    ("docker biuld", ("docker build", "docker typo")),
-    ("docker bulid", ("docker build", "docker typo")),
-    ("docker pull", ("docker pull", "docker typo")),
-    ("docker plul", ("docker pull", "docker typo")),
-    ("docker pussh", ("docker push", "docker typo")),
-    ("docker psuh", ("docker push", "docker typo")),
-    ("docker ps", ("docker ps", "docker typo")),
-    ("docker sp", ("docker ps", "docker typo")),
-    ("docker imgaes", ("docker images", "docker typo")),
-    ("docker iamges", ("docker images", "docker typo")),
-    ("docker imaegs", ("docker images", "docker typo")),
-    ("docker stopp", ("docker stop", "docker typo")),
-    ("docker statr", ("docker start", "docker typo")),
-    ("docker strat", ("docker start", "docker typo")),
-    ("docker exec", ("docker exec", "docker typo")),
-    ("docker exce", ("docker exec", "docker typo")),
-    ("docker logs", ("docker logs", "docker typo")),
-    ("docker lgo", ("docker logs", "docker typo")),
-    ("docker-compose", ("docker-compose", "docker typo")),
-    ("docker-compoes", ("docker-compose", "docker typo")),
-    ("docker-compsoe", ("docker-compose", "docker typo")),
+    ("dokcer", ("docker", "docker typo", None)),
+    ("doker", ("docker", "docker typo", None)),
+    ("dockre", ("docker", "docker typo", None)),
+    ("docker rnu", ("docker run", "docker typo", None)),
+    ("docker biuld", ("docker build", "docker typo", None)),
+    ("docker bulid", ("docker build", "docker typo", None)),
+    ("docker pull", ("docker pull", "docker typo", None)),
+    ("docker plul", ("docker pull", "docker typo", None)),
+    ("docker pussh", ("docker push", "docker typo", None)),
+    ("docker psuh", ("docker push", "docker typo", None)),
+    ("docker ps", ("docker ps", "docker typo", None)),
+    ("docker sp", ("docker ps", "docker typo", None)),
+    ("docker imgaes", ("docker images", "docker typo", None)),
+    ("docker iamges", ("docker images", "docker typo", None)),
+    ("docker imaegs", ("docker images", "docker typo", None)),
+    ("docker stopp", ("docker stop", "docker typo", None)),
+    ("docker statr", ("docker start", "docker typo", None)),
+    ("docker strat", ("docker start", "docker typo", None)),
+    ("docker exec", ("docker exec", "docker typo", None)),
+    ("docker exce", ("docker exec", "docker typo", None)),
+    ("docker logs", ("docker logs", "docker typo", None)),
+    ("docker lgo", ("docker logs", "docker typo", None)),
+    ("docker-compose", ("docker-compose", "docker typo", None)),
+    ("docker-compoes", ("docker-compose", "docker typo", None)),
+    ("docker-compsoe", ("docker-compose", "docker typo", None)),
     // ==================== KUBERNETES ====================
-    ("kubeclt", ("kubectl", "k8s typo")),
-    ("kubectll", ("kubectl", "k8s typo")),
-    ("kuberctl", ("kubectl", "k8s typo")),
-    ("kubectl get", ("kubectl get", "k8s typo")),
-    ("kubectl gt", ("kubectl get", "k8s typo")),
-    ("kubectl aplply", ("kubectl apply", "k8s typo")),
-    ("kubectl aply", ("kubectl apply", "k8s typo")),
-    ("kubectl delte", ("kubectl delete", "k8s typo")),
-    ("kubectl deelete", ("kubectl delete", "k8s typo")),
-    ("kubectl desribe", ("kubectl describe", "k8s typo")),
-    ("kubectl descirbe", ("kubectl describe", "k8s typo")),
-    ("kubectl logs", ("kubectl logs", "k8s typo")),
-    ("kubectl lgo", ("kubectl logs", "k8s typo")),
-    ("kubectl exec", ("kubectl exec", "k8s typo")),
-    ("kubectl exce", ("kubectl exec", "k8s typo")),
+    ("kubeclt", ("kubectl", "k8s typo", None)),
+    ("kubectll", ("kubectl", "k8s typo", None)),
+    ("kuberctl", ("kubectl", "k8s typo", None)),
+    ("kubectl get", ("kubectl get", "k8s typo", None)),
+    ("kubectl gt", ("kubectl get", "k8s typo", None)),
+    ("kubectl aplply", ("kubectl apply", "k8s typo", None)),
+    ("kubectl aply", ("kubectl apply", "k8s typo", None)),
+    ("kubectl delte", ("kubectl delete", "k8s typo", None)),
+    ("kubectl deelete", ("kubectl delete", "k8s typo", None)),
+    ("kubectl desribe", ("kubectl describe", "k8s typo", None)),
+    ("kubectl descirbe", ("kubectl describe", "k8s typo", None)),
+    ("kubectl logs", ("kubectl logs", "k8s typo", None)),
+    ("kubectl lgo", ("kubectl logs", "k8s typo", None)),
+    ("kubectl exec", ("kubectl exec", "k8s typo", None)),
+    ("kubectl exce", ("kubectl exec", "k8s typo", None)),
     // ==================== PYTHON ====================
-    ("pyhton", ("python", "python typo")),
-    ("pytohn", ("python", "python typo")),
-    ("ptyhon", ("python", "python typo")),
-    ("pyton", ("python", "python typo")),
-    ("pyhton3", ("python3", "python typo")),
-    ("pytohn3", ("python3", "python typo")),
-    ("python3", ("python3", "python typo")),
-    ("pip isntall", ("pip install", "pip typo")),
-    ("pip intall", ("pip install", "pip typo")),
-    ("pip onstall", ("pip install", "pip typo")),
-    ("pip instal", ("pip install", "pip typo")),
-    ("pip unintsall", ("pip uninstall", "pip typo")),
-    ("pip unistall", ("pip uninstall", "pip typo")),
-    ("pip freez", ("pip freeze", "pip typo")),
-    ("pip freeez", ("pip freeze", "pip typo")),
-    ("pip lsit", ("pip list", "pip typo")),
-    ("pip serach", ("pip search", "pip typo")),
-    ("pip searhc", ("pip search", "pip typo")),
+    ("pyhton", ("python", "python typo", None)),
+    ("pytohn", ("python", "python typo", None)),
+    ("ptyhon", ("python", "python typo", None)),
+    ("pyton", ("python", "python typo", None)),
+    ("pyhton3", ("python3", "python typo", None)),
+    ("pytohn3", ("python3", "python typo", None)),
+    ("python3", ("python3", "python typo", None)),
+    ("pip isntall", ("pip install", "pip typo", None)),
+    ("pip intall", ("pip install", "pip typo", None)),
+    ("pip onstall", ("pip install", "pip typo", None)),
+    ("pip instal", ("pip install", "pip typo", None)),
+    ("pip unintsall", ("pip uninstall", "pip typo", None)),
+    ("pip unistall", ("pip uninstall", "pip typo", None)),
+    ("pip freez", ("pip freeze", "pip typo", None)),
+    ("pip freeez", ("pip freeze", "pip typo", None)),
+    ("pip lsit", ("pip list", "pip typo", None)),
+    ("pip serach", ("pip search", "pip typo", None)),
+    ("pip searhc", ("pip search", "pip typo", None)),
     // ==================== LINUX/UNIX COMMANDS ====================
-    ("sl", ("ls", "classic typo")),
-    ("lss", ("ls", "ls typo")),
-    ("lls", ("ls", "ls typo")),
-    ("cd..", ("cd ..", "missing space")),
-    ("cd.", ("cd .", "missing space")),
-    ("cd-", ("cd -", "missing space")),
-    ("cd~", ("cd ~", "missing space")),
-    ("grpe", ("grep", "grep typo")),
-    ("gerp", ("grep", "grep typo")),
-    ("grp", ("grep", "grep typo")),
-    ("gti", ("git", "common typo")),
-    ("got", ("git", "common typo")),
-    ("claer", ("clear", "clear typo")),
-    ("cler", ("clear", "clear typo")),
-    ("clare", ("clear", "clear typo")),
-    ("clera", ("clear", "clear typo")),
-    ("exti", ("exit", "exit typo")),
-    ("eixt", ("exit", "exit typo")),
-    ("exitt", ("exit", "exit typo")),
-    ("hotp", ("htop", "htop typo")),
-    ("hotp", ("htop", "htop typo")),
-    ("tpo", ("top", "top typo")),
-    ("sduo", ("sudo", "sudo typo")),
-    ("suod", ("sudo", "sudo typo")),
-    ("sudp", ("sudo", "sudo typo")),
-    ("mkdri", ("mkdir", "mkdir typo")),
-    ("mkidr", ("mkdir", "mkdir typo")),
-    ("mkdr", ("mkdir", "mkdir typo")),
-    ("rmdir", ("rmdir", "rmdir typo")),
-    ("rmdri", ("rmdir", "rmdir typo")),
-    ("tuch", ("touch", "touch typo")),
-    ("touhc", ("touch", "touch typo")),
-    ("cat", ("cat", "cat typo")),
-    ("cta", ("cat", "cat typo")),
-    ("catt", ("cat", "cat typo")),
-    ("ehco", ("echo", "echo typo")),
-    ("ecoh", ("echo", "echo typo")),
-    ("echp", ("echo", "echo typo")),
-    ("mv", ("mv", "mv typo")),
-    ("vm", ("mv", "mv typo")),
-    ("mvv", ("mv", "mv typo")),
-    ("cp", ("cp", "cp typo")),
-    ("pc", ("cp", "cp typo")),
-    ("cpp", ("cp", "cp typo")),
-    ("rm", ("rm", "rm typo")),
-    ("mr", ("rm", "rm typo")),
-    ("rmm", ("rm", "rm typo")),
-    ("chmdo", ("chmod", "chmod typo")),
-    ("chmd", ("chmod", "chmod typo")),
-    ("chomd", ("chmod", "chmod typo")),
-    ("chonw", ("chown", "chown typo")),
-    ("chwon", ("chown", "chown typo")),
-    ("chonw", ("chown", "chown typo")),
-    ("pwd", ("pwd", "pwd typo")),
-    ("pwdd", ("pwd", "pwd typo")),
-    ("pdw", ("pwd", "pwd typo")),
-    ("tial", ("tail", "tail typo")),
-    ("taill", ("tail", "tail typo")),
-    ("head", ("head", "head typo")),
-    ("haed", ("head", "head typo")),
-    ("headd", ("head", "head typo")),
-    ("wegt", ("wget", "wget typo")),
-    ("wgte", ("wget", "wget typo")),
-    ("wetg", ("wget", "wget typo")),
-    ("curl", ("curl", "curl typo")),
-    ("culr", ("curl", "curl typo")),
-    ("crlu", ("curl", "curl typo")),
-    ("curlll", ("curl", "curl typo")),
-    ("vin", ("vim", "vim typo")),
-    ("vmi", ("vim", "vim typo")),
-    ("vmm", ("vim", "vim typo")),
-    ("nano", ("nano", "nano typo")),
-    ("nao", ("nano", "nano typo")),
-    ("naon", ("nano", "nano typo")),
-    ("fdin", ("find", "find typo")),
-    ("fnid", ("find", "find typo")),
-    ("fidn", ("find", "find typo")),
-    ("ssh", ("ssh", "ssh typo")),
-    ("shh", ("ssh", "ssh typo")),
-    ("shs", ("ssh", "ssh typo")),
-    ("scp", ("scp", "scp typo")),
-    ("scpp", ("scp", "scp typo")),
-    ("pign", ("ping", "ping typo")),
-    ("pnig", ("ping", "ping typo")),
-    ("pingg", ("ping", "ping typo")),
-    ("tracertoue", ("traceroute", "traceroute typo")),
-    ("tracroute", ("traceroute", "traceroute typo")),
-    ("netstat", ("netstat", "netstat typo")),
-    ("nestat", ("netstat", "netstat typo")),
-    ("netsta", ("netstat", "netstat typo")),
-    ("systemclt", ("systemctl", "systemctl typo")),
-    ("systemtcl", ("systemctl", "systemctl typo")),
-    ("systmectl", ("systemctl", "systemctl typo")),
-    ("servcie", ("service", "service typo")),
-    ("serivce", ("service", "service typo")),
-    ("srevice", ("service", "service typo")),
-    ("reboot", ("reboot", "reboot typo")),
-    ("reboto", ("reboot", "reboot typo")),
-    ("reobot", ("reboot", "reboot typo")),
-    ("shutdwon", ("shutdown", "shutdown typo")),
-    ("shutdonw", ("shutdown", "shutdown typo")),
-    ("shudown", ("shutdown", "shutdown typo")),
+    ("sl", ("ls", "classic typo", Some(0.8))),
+    ("lss", ("ls", "ls typo", None)),
+    ("lls", ("ls", "ls typo", None)),
+    ("cd..", ("cd ..", "missing space", None)),
+    ("cd.", ("cd .", "missing space", None)),
+    ("cd-", ("cd -", "missing space", None)),
+    ("cd~", ("cd ~", "missing space", None)),
+    ("grpe", ("grep", "grep typo", None)),
+    ("gerp", ("grep", "grep typo", None)),
+    ("grp", ("grep", "grep typo", Some(0.9))),
+    ("gti", ("git", "common typo", None)),
+    ("got", ("git", "common typo", Some(0.9))),
+    ("claer", ("clear", "clear typo", None)),
+    ("cler", ("clear", "clear typo", None)),
+    ("clare", ("clear", "clear typo", None)),
+    ("clera", ("clear", "clear typo", None)),
+    ("exti", ("exit", "exit typo", None)),
+    ("eixt", ("exit", "exit typo", None)),
+    ("exitt", ("exit", "exit typo", None)),
+    ("hotp", ("htop", "htop typo", None)),
+    ("hotp", ("htop", "htop typo", None)),
+    ("tpo", ("top", "top typo", None)),
+    ("sduo", ("sudo", "sudo typo", None)),
+    ("suod", ("sudo", "sudo typo", None)),
+    ("sudp", ("sudo", "sudo typo", None)),
+    ("mkdri", ("mkdir", "mkdir typo", None)),
+    ("mkidr", ("mkdir", "mkdir typo", None)),
+    ("mkdr", ("mkdir", "mkdir typo", None)),
+    ("rmdir", ("rmdir", "rmdir typo", None)),
+    ("rmdri", ("rmdir", "rmdir typo", None)),
+    ("tuch", ("touch", "touch typo", None)),
+    ("touhc", ("touch", "touch typo", None)),
+    ("cat", ("cat", "cat typo", None)),
+    ("cta", ("cat", "cat typo", None)),
+    ("catt", ("cat", "cat typo", None)),
+    ("ehco", ("echo", "echo typo", None)),
+    ("ecoh", ("echo", "echo typo", None)),
+    ("echp", ("echo", "echo typo", None)),
+    ("mv", ("mv", "mv typo", None)),
+    ("vm", ("mv", "mv typo", Some(0.8))),
+    ("mvv", ("mv", "mv typo", None)),
+    ("cp", ("cp", "cp typo", None)),
+    ("pc", ("cp", "cp typo", Some(0.8))),
+    ("cpp", ("cp", "cp typo", Some(0.6))),
+    ("rm", ("rm", "rm typo", None)),
+    ("mr", ("rm", "rm typo", Some(0.7))),
+    ("rmm", ("rm", "rm typo", None)),
+    ("chmdo", ("chmod", "chmod typo", None)),
+    ("chmd", ("chmod", "chmod typo", None)),
+    ("chomd", ("chmod", "chmod typo", None)),
+    ("chonw", ("chown", "chown typo", None)),
+    ("chwon", ("chown", "chown typo", None)),
+    ("chonw", ("chown", "chown typo", None)),
+    ("pwd", ("pwd", "pwd typo", None)),
+    ("pwdd", ("pwd", "pwd typo", None)),
+    ("pdw", ("pwd", "pwd typo", None)),
+    ("tial", ("tail", "tail typo", None)),
+    ("taill", ("tail", "tail typo", None)),
+    ("head", ("head", "head typo", None)),
+    ("haed", ("head", "head typo", None)),
+    ("headd", ("head", "head typo", None)),
+    ("wegt", ("wget", "wget typo", None)),
+    ("wgte", ("wget", "wget typo", None)),
+    ("wetg", ("wget", "wget typo", None)),
+    ("curl", ("curl", "curl typo", None)),
+    ("culr", ("curl", "curl typo", None)),
+    ("crlu", ("curl", "curl typo", None)),
+    ("curlll", ("curl", "curl typo", None)),
+    ("vin", ("vim", "vim typo", None)),
+    ("vmi", ("vim", "vim typo", None)),
+    ("vmm", ("vim", "vim typo", None)),
+    ("nano", ("nano", "nano typo", None)),
+    ("nao", ("nano", "nano typo", None)),
+    ("naon", ("nano", "nano typo", None)),
+    ("fdin", ("find", "find typo", None)),
+    ("fnid", ("find", "find typo", None)),
+    ("fidn", ("find", "find typo", None)),
+    ("ssh", ("ssh", "ssh typo", None)),
+    ("shh", ("ssh", "ssh typo", None)),
+    ("shs", ("ssh", "ssh typo", None)),
+    ("scp", ("scp", "scp typo", None)),
+    ("scpp", ("scp", "scp typo", None)),
+    ("pign", ("ping", "ping typo", None)),
+    ("pnig", ("ping", "ping typo", None)),
+    ("pingg", ("ping", "ping typo", None)),
+    ("tracertoue", ("traceroute", "traceroute typo", None)),
+    ("tracroute", ("traceroute", "traceroute typo", None)),
+    ("netstat", ("netstat", "netstat typo", None)),
+    ("nestat", ("netstat", "netstat typo", None)),
+    ("netsta", ("netstat", "netstat typo", None)),
+    ("systemclt", ("systemctl", "systemctl typo", None)),
+    ("systemtcl", ("systemctl", "systemctl typo", None)),
+    ("systmectl", ("systemctl", "systemctl typo", None)),
+    ("servcie", ("service", "service typo", None)),
+    ("serivce", ("service", "service typo", None)),
+    ("srevice", ("service", "service typo", None)),
+    ("reboot", ("reboot", "reboot typo", None)),
+    ("reboto", ("reboot", "reboot typo", None)),
+    ("reobot", ("reboot", "reboot typo", None)),
+    ("shutdwon", ("shutdown", "shutdown typo", None)),
+    ("shutdonw", ("shutdown", "shutdown typo", None)),
+    ("shudown", ("shutdown", "shutdown typo", None)),
     // ==================== MAKE/BUILD ====================
-    ("make", ("make", "make typo")),
-    ("mak", ("make", "make typo")),
-    ("mkae", ("make", "make typo")),
-    ("amke", ("make", "make typo")),
-    ("make biuld", ("make build", "make typo")),
-    ("make clen", ("make clean", "make typo")),
-    ("make isntall", ("make install", "make typo")),
-    ("make tset", ("make test", "make typo")),
-    ("cmake", ("cmake", "cmake typo")),
-    ("cmkae", ("cmake", "cmake typo")),
-    ("camke", ("cmake", "cmake typo")),
+    ("make", ("make", "make typo", None)),
+    ("mak", ("make", "make typo", None)),
+    ("mkae", ("make", "make typo", None)),
+    ("amke", ("make", "make typo", None)),
+    ("make biuld", ("make build", "make typo", None)),
+    ("make clen", ("make clean", "make typo", None)),
+    ("make isntall", ("make install", "make typo", None)),
+    ("make tset", ("make test", "make typo", None)),
+    ("cmake", ("cmake", "cmake typo", None)),
+    ("cmkae", ("cmake", "cmake typo", None)),
+    ("camke", ("cmake", "cmake typo", None)),
     // ==================== TERRAFORM ====================
-    ("terraform", ("terraform", "tf typo")),
-    ("terrafomr", ("terraform", "tf typo")),
-    ("terrafrom", ("terraform", "tf typo")),
-    ("teraform", ("terraform", "tf typo")),
-    ("terraform inint", ("terraform init", "tf typo")),
-    ("terraform inti", ("terraform init", "tf typo")),
-    ("terraform paln", ("terraform plan", "tf typo")),
-    ("terraform plna", ("terraform plan", "tf typo")),
-    ("terraform aplply", ("terraform apply", "tf typo")),
-    ("terraform aply", ("terraform apply", "tf typo")),
-    ("terraform destory", ("terraform destroy", "tf typo")),
-    ("terraform desrtoy", ("terraform destroy", "tf typo")),
+    ("terraform", ("terraform", "tf typo", None)),
+    ("terrafomr", ("terraform", "tf typo", None)),
+    ("terrafrom", ("terraform", "tf typo", None)),
+    ("teraform", ("terraform", "tf typo", None)),
+    ("terraform inint", ("terraform init", "tf typo", None)),
+    ("terraform inti", ("terraform init", "tf typo", None)),
+    ("terraform paln", ("terraform plan", "tf typo", None)),
+    ("terraform plna", ("terraform plan", "tf typo", None)),
+    ("terraform aplply", ("terraform apply", "tf typo", None)),
+    ("terraform aply", ("terraform apply", "tf typo", None)),
+    ("terraform destory", ("terraform destroy", "tf typo", None)),
+    ("terraform desrtoy", ("terraform destroy", "tf typo", None)),
     // ==================== AWS CLI ====================
-    ("aws s3", ("aws s3", "aws typo")),
-    ("aws 3s", ("aws s3", "aws typo")),
-    ("aws ec2", ("aws ec2", "aws typo")),
-    ("aws 2ec", ("aws ec2", "aws typo")),
-    ("aws lambda", ("aws lambda", "aws typo")),
-    ("aws lamda", ("aws lambda", "aws typo")),
-    ("aws labmda", ("aws lambda", "aws typo")),
+    ("aws s3", ("aws s3", "aws typo", None)),
+    ("aws 3s", ("aws s3", "aws typo", None)),
+    ("aws ec2", ("aws ec2", "aws typo", None)),
+    ("aws 2ec", ("aws ec2", "aws typo", None)),
+    ("aws lambda", ("aws lambda", "aws typo", None)),
+    ("aws lamda", ("aws lambda", "aws typo", None)),
+    ("aws labmda", ("aws lambda", "aws typo", None)),
     // ==================== MISC DEV TOOLS ====================
-    ("rustc", ("rustc", "rustc typo")),
-    ("rsutc", ("rustc", "rustc typo")),
-    ("rustup", ("rustup", "rustup typo")),
-    ("rsutup", ("rustup", "rustup typo")),
-    ("rustpu", ("rustup", "rustup typo")),
-    ("nvm", ("nvm", "nvm typo")),
-    ("nmv", ("nvm", "nvm typo")),
-    ("nvmm", ("nvm", "nvm typo")),
-    ("nodemon", ("nodemon", "nodemon typo")),
-    ("nodeomn", ("nodemon", "nodemon typo")),
-    ("nodemno", ("nodemon", "nodemon typo")),
-    ("tsc", ("tsc", "tsc typo")),
-    ("tscc", ("tsc", "tsc typo")),
-    ("tsx", ("tsx", "tsx typo")),
-    ("tsxx", ("tsx", "tsx typo")),
-    ("ng", ("ng", "angular typo")),
-    ("ngg", ("ng", "angular typo")),
-    ("nuxt", ("nuxt", "nuxt typo")),
-    ("nxut", ("nuxt", "nuxt typo")),
-    ("vite", ("vite", "vite typo")),
-    ("vtie", ("vite", "vite typo")),
-    ("viite", ("vite", "vite typo")),
+    ("rustc", ("rustc", "rustc typo", None)),
+    ("rsutc", ("rustc", "rustc typo", None)),
+    ("rustup", ("rustup", "rustup typo", None)),
+    ("rsutup", ("rustup", "rustup typo", None)),
+    ("rustpu", ("rustup", "rustup typo", None)),
+    ("nvm", ("nvm", "nvm typo", None)),
+    ("nmv", ("nvm", "nvm typo", None)),
+    ("nvmm", ("nvm", "nvm typo", None)),
+    ("nodemon", ("nodemon", "nodemon typo", None)),
+    ("nodeomn", ("nodemon", "nodemon typo", None)),
+    ("nodemno", ("nodemon", "nodemon typo", None)),
+    ("tsc", ("tsc", "tsc typo", None)),
+    ("tscc", ("tsc", "tsc typo", None)),
+    ("tsx", ("tsx", "tsx typo", None)),
+    ("tsxx", ("tsx", "tsx typo", None)),
+    ("ng", ("ng", "angular typo", None)),
+    ("ngg", ("ng", "angular typo", None)),
+    ("nuxt", ("nuxt", "nuxt typo", None)),
+    ("nxut", ("nuxt", "nuxt typo", None)),
+    ("vite", ("vite", "vite typo", None)),
+    ("vtie", ("vite", "vite typo", None)),
+    ("viite", ("vite", "vite typo", None)),
   ]
 }
 
@@ -498,3 +502,434 @@ pub fn get_common_commands() -> Vec<&'static str> {
     "helm",
   ]
 }
+
+// Primary commands and the subcommands (verbs) that typically follow them
+pub fn get_subcommands() -> Vec<(&'static str, Vec<&'static str>)> {
+  vec![
+    (
+      "git",
+      vec![
+        "add",
+        "branch",
+        "checkout",
+        "cherry-pick",
+        "clone",
+        "commit",
+        "diff",
+        "fetch",
+        "init",
+        "log",
+        "merge",
+        "pull",
+        "push",
+        "rebase",
+        "remote",
+        "reset",
+        "restore",
+        "revert",
+        "show",
+        "stash",
+        "status",
+        "switch",
+        "tag",
+      ],
+    ),
+    (
+      "docker",
+      vec![
+        "build", "compose", "exec", "images", "inspect", "kill", "login", "logs", "ps", "pull",
+        "push", "restart", "rm", "rmi", "run", "start", "stop", "tag", "volume", "network",
+      ],
+    ),
+    (
+      "kubectl",
+      vec![
+        "apply",
+        "create",
+        "delete",
+        "describe",
+        "edit",
+        "exec",
+        "get",
+        "logs",
+        "port-forward",
+        "rollout",
+        "scale",
+      ],
+    ),
+    (
+      "cargo",
+      vec![
+        "add",
+        "bench",
+        "build",
+        "check",
+        "clean",
+        "clippy",
+        "doc",
+        "fetch",
+        "fix",
+        "fmt",
+        "init",
+        "install",
+        "login",
+        "metadata",
+        "new",
+        "package",
+        "publish",
+        "remove",
+        "run",
+        "search",
+        "test",
+        "tree",
+        "uninstall",
+        "update",
+        "vendor",
+        "yank",
+      ],
+    ),
+    (
+      "npm",
+      vec![
+        "access",
+        "adduser",
+        "audit",
+        "cache",
+        "ci",
+        "config",
+        "dedupe",
+        "deprecate",
+        "exec",
+        "explain",
+        "fund",
+        "help",
+        "init",
+        "install",
+        "link",
+        "list",
+        "login",
+        "logout",
+        "outdated",
+        "pack",
+        "prune",
+        "publish",
+        "rebuild",
+        "restart",
+        "run",
+        "run-script",
+        "search",
+        "start",
+        "stop",
+        "test",
+        "uninstall",
+        "unpublish",
+        "update",
+        "version",
+        "view",
+        "whoami",
+      ],
+    ),
+    (
+      "yarn",
+      vec![
+        "add",
+        "audit",
+        "bin",
+        "cache",
+        "config",
+        "create",
+        "dedupe",
+        "dlx",
+        "exec",
+        "global",
+        "info",
+        "init",
+        "install",
+        "link",
+        "list",
+        "login",
+        "outdated",
+        "pack",
+        "publish",
+        "remove",
+        "run",
+        "unlink",
+        "upgrade",
+        "upgrade-interactive",
+        "version",
+        "why",
+        "workspace",
+        "workspaces",
+      ],
+    ),
+    (
+      "pnpm",
+      vec![
+        "add",
+        "audit",
+        "create",
+        "dedupe",
+        "dlx",
+        "exec",
+        "fetch",
+        "import",
+        "init",
+        "install",
+        "link",
+        "list",
+        "outdated",
+        "pack",
+        "prune",
+        "publish",
+        "rebuild",
+        "remove",
+        "root",
+        "run",
+        "start",
+        "store",
+        "test",
+        "uninstall",
+        "unlink",
+        "update",
+        "why",
+      ],
+    ),
+    (
+      "apt",
+      vec![
+        "autoremove",
+        "install",
+        "purge",
+        "remove",
+        "search",
+        "show",
+        "update",
+        "upgrade",
+      ],
+    ),
+    (
+      "apt-get",
+      vec![
+        "autoremove",
+        "install",
+        "purge",
+        "remove",
+        "update",
+        "upgrade",
+      ],
+    ),
+    (
+      "brew",
+      vec![
+        "info",
+        "install",
+        "list",
+        "search",
+        "uninstall",
+        "update",
+        "upgrade",
+      ],
+    ),
+    (
+      "pip",
+      vec!["download", "freeze", "install", "list", "show", "uninstall"],
+    ),
+  ]
+}
+
+// Package managers and their verbs, used to fix `apt isntall curl` and `aptinstall curl`
+pub fn get_package_manager_verbs() -> Vec<(&'static str, Vec<&'static str>)> {
+  vec![
+    (
+      "apt",
+      vec![
+        "autoremove",
+        "install",
+        "list",
+        "purge",
+        "reinstall",
+        "remove",
+        "search",
+        "show",
+        "update",
+        "upgrade",
+      ],
+    ),
+    (
+      "apt-get",
+      vec![
+        "autoremove",
+        "clean",
+        "dist-upgrade",
+        "install",
+        "purge",
+        "remove",
+        "update",
+        "upgrade",
+      ],
+    ),
+    (
+      "yum",
+      vec![
+        "check-update",
+        "info",
+        "install",
+        "list",
+        "reinstall",
+        "remove",
+        "search",
+        "update",
+        "upgrade",
+      ],
+    ),
+    (
+      "dnf",
+      vec![
+        "autoremove",
+        "check-update",
+        "info",
+        "install",
+        "list",
+        "reinstall",
+        "remove",
+        "search",
+        "update",
+        "upgrade",
+      ],
+    ),
+  ]
+}
+
+// Flags each command accepts, used to spot a flag typed with the wrong number of dashes
+pub fn get_flags() -> Vec<(&'static str, Vec<&'static str>)> {
+  vec![
+    (
+      "git",
+      vec![
+        "-a",
+        "-b",
+        "-d",
+        "-D",
+        "-f",
+        "-m",
+        "-p",
+        "-u",
+        "-v",
+        "--all",
+        "--amend",
+        "--branch",
+        "--cached",
+        "--depth",
+        "--force",
+        "--global",
+        "--hard",
+        "--help",
+        "--message",
+        "--no-edit",
+        "--oneline",
+        "--patch",
+        "--rebase",
+        "--set-upstream",
+        "--soft",
+        "--staged",
+        "--stat",
+        "--verbose",
+        "--version",
+      ],
+    ),
+    (
+      "ls",
+      vec![
+        "-a",
+        "-h",
+        "-l",
+        "-r",
+        "-t",
+        "-R",
+        "-S",
+        "--all",
+        "--almost-all",
+        "--color",
+        "--directory",
+        "--help",
+        "--human-readable",
+        "--recursive",
+        "--reverse",
+        "--size",
+        "--sort",
+        "--version",
+      ],
+    ),
+  ]
+}
+
+// Programs that take over the terminal (editors, pagers, REPLs, remote shells)
+pub fn get_interactive_commands() -> Vec<&'static str> {
+  vec![
+    "emacs", "fzf", "htop", "hx", "less", "man", "micro", "more", "mysql", "nano", "nvim", "psql",
+    "screen", "sqlite3", "ssh", "tmux", "top", "vi", "vim",
+  ]
+}
+
+// Commands that are hard to undo, as (regex, what it does). Matched anywhere in the
+// command line, so `sudo` and chains are covered.
+pub fn get_dangerous_patterns() -> Vec<(&'static str, &'static str)> {
+  vec![
+    (
+      r"\brm\s+(-\S*[rRf]|--recursive|--force)",
+      "deletes files recursively or without asking",
+    ),
+    (r"\bdd\b.*\bof=", "overwrites a file or disk"),
+    (r"\bmkfs(\.\w+)?\b", "formats a filesystem"),
+    (r">\s*/dev/(sd|nvme|disk|hd)", "writes to a disk device"),
+    (
+      r"\bgit\s+push\b.*\s(-f|--force)",
+      "force-pushes over remote history",
+    ),
+    (r"\bgit\s+reset\s+--hard\b", "discards uncommitted changes"),
+    (r"\bgit\s+clean\s+-\S*f", "deletes untracked files"),
+    (
+      r"\b(chmod|chown)\s+(-\S*R|--recursive)",
+      "changes permissions recursively",
+    ),
+    (
+      r"\b(shutdown|reboot|poweroff|halt)\b",
+      "shuts down or restarts the machine",
+    ),
+    (r"(?i)\bdrop\s+(table|database)\b", "drops a database table"),
+    (r"\bkubectl\s+delete\b", "deletes Kubernetes resources"),
+    (
+      r"\bdocker\s+(system|volume)\s+prune\b",
+      "deletes Docker data",
+    ),
+    (
+      r"(?i)\bRemove-Item\b.*-Recurse",
+      "deletes files recursively",
+    ),
+    (
+      r"(?i)\b(del|rd|rmdir)\s+.*/[sq]\b",
+      "deletes files recursively or without asking",
+    ),
+    (r"(?i)\bformat\s+[a-z]:", "formats a drive"),
+  ]
+}
+
+// Words after the command that make it open an editor or prompt (`git rebase -i`, `git add -p`)
+pub fn get_interactive_args() -> Vec<(&'static str, Vec<&'static str>)> {
+  vec![
+    (
+      "git",
+      vec![
+        "-i",
+        "-p",
+        "--interactive",
+        "--patch",
+        "commit",
+        "difftool",
+        "mergetool",
+      ],
+    ),
+    ("docker", vec!["-it", "-ti"]),
+    ("kubectl", vec!["-it", "-ti", "edit"]),
+  ]
+}
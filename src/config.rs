@@ -1,10 +1,22 @@
+use crate::ai::{AiProvider, MAX_AI_CANDIDATES};
+use crate::commands::get_common_fixes;
+use crate::corrections::has_placeholders;
+use crate::error::WtfError;
+use crate::history::ShellType;
 use serde::{Deserialize, Serialize};
+use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
-#[derive(Debug, Serialize, Deserialize)]
+// Set once from `--config` at startup; takes precedence over `WTF_CONFIG`.
+static CONFIG_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+// A misspelled setting is an error rather than silently ignored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct UserConfig {
-  pub custom_typos: Vec<(String, String)>,
+  pub custom_typos: Vec<CustomTypo>,
   #[serde(default)]
   pub first_run_complete: bool,
   #[serde(default)]
@@ -13,6 +25,235 @@ pub struct UserConfig {
   pub ai_mode: bool,
   #[serde(default)]
   pub google_api_key: Option<String>,
+  /// Extra keys tried in order when the previous one is rate limited
+  #[serde(default)]
+  pub google_api_keys: Vec<String>,
+  #[serde(default)]
+  pub learn_fuzzy: bool,
+  /// Remember accepted fuzzy and AI fixes for the current project (git root or directory)
+  #[serde(default)]
+  pub remember_per_directory: bool,
+  #[serde(default)]
+  pub exec_allowlist: Vec<String>,
+  /// Harmless commands (`ls`, `git status`) run without asking when they're the only fix
+  #[serde(default)]
+  pub safe_commands: Vec<String>,
+  #[serde(default)]
+  pub history_sources: Vec<String>,
+  /// Fix the latest failed command the shell hook recorded instead of the latest one
+  #[serde(default)]
+  pub prefer_failed: bool,
+  #[serde(default = "default_ai_max_input_chars")]
+  pub ai_max_input_chars: usize,
+  /// Longest Gemini rate limit to wait out before retrying once; 0 never waits
+  #[serde(default = "default_ai_retry_wait_secs")]
+  pub ai_retry_wait_secs: u64,
+  /// Alternative fixes to ask Gemini for in one request, shown as a list to pick from
+  #[serde(default = "default_ai_candidate_count")]
+  pub ai_candidate_count: usize,
+  /// Stop with the error when AI fails instead of falling back to pattern matching
+  #[serde(default)]
+  pub ai_no_fallback: bool,
+  #[serde(default)]
+  pub ai_fallback_provider: Option<String>,
+  #[serde(default)]
+  pub ollama_url: Option<String>,
+  #[serde(default)]
+  pub ollama_model: Option<String>,
+  #[serde(default = "default_true")]
+  pub normalize_unicode: bool,
+  /// Set a trailing `&` aside while correcting and put it back on every fix
+  #[serde(default = "default_true")]
+  pub strip_background: bool,
+  #[serde(default)]
+  pub auto_confidence_threshold: f64,
+  /// Run the fix without asking only when it's the single suggestion at 100% confidence
+  #[serde(default)]
+  pub smart_auto: bool,
+  #[serde(default)]
+  pub ascii_only: Option<bool>,
+  /// Custom typo categories to use. Empty means all of them.
+  #[serde(default)]
+  pub active_categories: Vec<String>,
+  /// Let rules list directories to fix typos in file arguments
+  #[serde(default)]
+  pub fuzzy_paths: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMode {
+  /// The typo is the whole command or its leading words
+  #[default]
+  Prefix,
+  /// The typo is replaced wherever it appears as a whole word
+  Word,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "CustomTypoRepr", into = "CustomTypoRepr")]
+pub struct CustomTypo {
+  pub wrong: String,
+  pub correct: String,
+  pub match_mode: MatchMode,
+  /// Match the typo in any case; the fix is always written as given
+  pub ignore_case: bool,
+  /// Groups related fixes (e.g. "git") so they can be listed or turned off together
+  pub category: Option<String>,
+}
+
+impl CustomTypo {
+  pub fn new(wrong: String, correct: String) -> Self {
+    Self {
+      wrong,
+      correct,
+      match_mode: MatchMode::Prefix,
+      ignore_case: false,
+      category: None,
+    }
+  }
+}
+
+/// One area of `UserConfig::check`; no problems means it passed.
+pub struct ConfigCheck {
+  pub name: &'static str,
+  pub problems: Vec<String>,
+}
+
+impl ConfigCheck {
+  fn new(name: &'static str, problems: Vec<String>) -> Self {
+    Self { name, problems }
+  }
+}
+
+/// Why a custom typo isn't pulling its weight, see `UserConfig::check_typos`.
+pub enum TypoIssue {
+  /// A built-in fix already maps the same typo to the same command
+  Redundant,
+  /// A built-in fix maps the same typo to this other command
+  Conflicts(&'static str),
+  /// An earlier custom typo (this index) has the same wrong command
+  Shadowed(usize),
+  /// The fix is the typo itself
+  NoOp,
+  /// Its category isn't in active_categories
+  Inactive,
+}
+
+// Plain prefix typos keep the original `["wrong", "correct"]` form so existing
+// config files stay unchanged; anything with options is written as an object.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum CustomTypoRepr {
+  Pair(String, String),
+  Entry(CustomTypoEntry),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CustomTypoEntry {
+  wrong: String,
+  correct: String,
+  #[serde(default, rename = "match", skip_serializing_if = "is_prefix")]
+  match_mode: MatchMode,
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  ignore_case: bool,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  category: Option<String>,
+}
+
+fn is_prefix(mode: &MatchMode) -> bool {
+  *mode == MatchMode::Prefix
+}
+
+impl From<CustomTypoRepr> for CustomTypo {
+  fn from(repr: CustomTypoRepr) -> Self {
+    match repr {
+      CustomTypoRepr::Pair(wrong, correct) => CustomTypo::new(wrong, correct),
+      CustomTypoRepr::Entry(CustomTypoEntry {
+        wrong,
+        correct,
+        match_mode,
+        ignore_case,
+        category,
+      }) => CustomTypo {
+        wrong,
+        correct,
+        match_mode,
+        ignore_case,
+        category,
+      },
+    }
+  }
+}
+
+impl From<CustomTypo> for CustomTypoRepr {
+  fn from(typo: CustomTypo) -> Self {
+    if typo.match_mode == MatchMode::Prefix && !typo.ignore_case && typo.category.is_none() {
+      return CustomTypoRepr::Pair(typo.wrong, typo.correct);
+    }
+
+    CustomTypoRepr::Entry(CustomTypoEntry {
+      wrong: typo.wrong,
+      correct: typo.correct,
+      match_mode: typo.match_mode,
+      ignore_case: typo.ignore_case,
+      category: typo.category,
+    })
+  }
+}
+
+// serde's message without its " at line N column M", plus a likely fix for the usual
+// mistakes: a misspelled setting or a malformed custom typo.
+fn describe_parse_error(error: &serde_json::Error) -> String {
+  let message = error.to_string();
+  let location = format!(" at line {} column {}", error.line(), error.column());
+  let message = message.strip_suffix(&location).unwrap_or(&message);
+
+  if message.contains("untagged enum CustomTypoRepr") {
+    return "a custom typo must be [\"wrong\", \"correct\"] or an object with \"wrong\", \"correct\" and optionally \"match\", \"ignore_case\", \"category\"".to_string();
+  }
+
+  let Some(field) = message
+    .strip_prefix("unknown field `")
+    .and_then(|rest| rest.split('`').next())
+  else {
+    return message.to_string();
+  };
+
+  // Listing every setting, as serde does, buries the one that matters
+  match closest_setting(field) {
+    Some(setting) => format!("unknown setting '{}', did you mean '{}'?", field, setting),
+    None => format!("unknown setting '{}'", field),
+  }
+}
+
+fn closest_setting(field: &str) -> Option<String> {
+  let defaults = serde_json::to_value(UserConfig::default()).ok()?;
+
+  defaults
+    .as_object()?
+    .keys()
+    .map(|name| (name, strsim::jaro_winkler(field, name)))
+    .filter(|(_, similarity)| *similarity > 0.8)
+    .max_by(|a, b| a.1.total_cmp(&b.1))
+    .map(|(name, _)| name.clone())
+}
+
+fn default_true() -> bool {
+  true
+}
+
+fn default_ai_max_input_chars() -> usize {
+  2000
+}
+
+fn default_ai_retry_wait_secs() -> u64 {
+  10
+}
+
+fn default_ai_candidate_count() -> usize {
+  1
 }
 
 impl Default for UserConfig {
@@ -23,58 +264,98 @@ impl Default for UserConfig {
       auto_mode: false,
       ai_mode: false,
       google_api_key: None,
+      google_api_keys: Vec::new(),
+      learn_fuzzy: false,
+      remember_per_directory: false,
+      exec_allowlist: Vec::new(),
+      safe_commands: Vec::new(),
+      history_sources: Vec::new(),
+      prefer_failed: false,
+      ai_max_input_chars: default_ai_max_input_chars(),
+      ai_retry_wait_secs: default_ai_retry_wait_secs(),
+      ai_candidate_count: default_ai_candidate_count(),
+      ai_no_fallback: false,
+      ai_fallback_provider: None,
+      ollama_url: None,
+      ollama_model: None,
+      normalize_unicode: true,
+      strip_background: true,
+      auto_confidence_threshold: 0.0,
+      smart_auto: false,
+      ascii_only: None,
+      active_categories: Vec::new(),
+      fuzzy_paths: false,
     }
   }
 }
 
 impl UserConfig {
-  pub fn load() -> Self {
-    match Self::config_path() {
-      Ok(path) => {
-        if path.exists() {
-          if let Ok(content) = fs::read_to_string(&path) {
-            if let Ok(config) = serde_json::from_str(&content) {
-              return config;
-            }
-          }
-        }
-        Self::default()
-      }
-      Err(_) => Self::default(),
+  /// The config, or the defaults when there's no config file (or nowhere to look for one).
+  /// A file that can't be read or parsed is an error that says where it went wrong,
+  /// never the defaults, so a later `save` can't overwrite it.
+  pub fn try_load() -> Result<Self, WtfError> {
+    let path = match Self::config_path() {
+      Ok(path) => path,
+      Err(_) => return Ok(Self::default()),
+    };
+
+    if !path.exists() {
+      return Ok(Self::default());
     }
+
+    Self::parse_file(&path)
   }
 
-  pub fn save(&self) -> Result<(), String> {
-    let path = Self::config_path()?;
+  fn parse_file(path: &Path) -> Result<Self, WtfError> {
+    let content = fs::read_to_string(path).map_err(|e| {
+      WtfError::ConfigError(format!("Failed to read config {}: {}", path.display(), e))
+    })?;
 
+    serde_json::from_str(&content).map_err(|e| {
+      WtfError::ConfigError(format!(
+        "Invalid config {}, line {} column {}: {}",
+        path.display(),
+        e.line(),
+        e.column(),
+        describe_parse_error(&e)
+      ))
+    })
+  }
+
+  pub fn save(&self) -> Result<(), WtfError> {
+    self.save_to(&Self::config_path()?)
+  }
+
+  fn save_to(&self, path: &Path) -> Result<(), WtfError> {
     if let Some(parent) = path.parent() {
       fs::create_dir_all(parent)
-        .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        .map_err(|e| WtfError::ConfigError(format!("Failed to create config directory: {}", e)))?;
     }
 
     let content = serde_json::to_string_pretty(self)
-      .map_err(|e| format!("Failed to serialize config: {}", e))?;
+      .map_err(|e| WtfError::ConfigError(format!("Failed to serialize config: {}", e)))?;
 
-    fs::write(&path, content).map_err(|e| format!("Failed to write config: {}", e))?;
+    fs::write(path, content)
+      .map_err(|e| WtfError::ConfigError(format!("Failed to write config: {}", e)))?;
 
     Ok(())
   }
 
-  pub fn add_typo(&mut self, wrong: String, correct: String) {
-    self.custom_typos.retain(|(w, _)| w != &wrong);
+  pub fn add_typo(&mut self, typo: CustomTypo) {
+    self.custom_typos.retain(|t| t.wrong != typo.wrong);
 
-    self.custom_typos.push((wrong, correct));
+    self.custom_typos.push(typo);
   }
 
   pub fn remove_typo(&mut self, wrong: &str) -> bool {
     let original_len = self.custom_typos.len();
-    self.custom_typos.retain(|(w, _)| w != wrong);
+    self.custom_typos.retain(|t| t.wrong != wrong);
     self.custom_typos.len() < original_len
   }
 
-  pub fn add_from_builtin(&mut self, wrong: String, correct: String) {
-    if !self.custom_typos.iter().any(|(w, _)| w == &wrong) {
-      self.custom_typos.push((wrong, correct));
+  pub fn add_from_builtin(&mut self, typo: CustomTypo) {
+    if !self.custom_typos.iter().any(|t| t.wrong == typo.wrong) {
+      self.custom_typos.push(typo);
     }
   }
 
@@ -82,6 +363,10 @@ impl UserConfig {
     self.first_run_complete = true;
   }
 
+  pub fn reset_first_run(&mut self) {
+    self.first_run_complete = false;
+  }
+
   pub fn set_auto_mode(&mut self, enabled: bool) {
     self.auto_mode = enabled;
   }
@@ -100,17 +385,232 @@ impl UserConfig {
     self.ai_mode
   }
 
-  pub fn set_google_api_key(&mut self, key: String) {
-    self.google_api_key = Some(key);
+  // The first key stays in `google_api_key` so configs still work with older versions.
+  pub fn set_google_api_keys(&mut self, mut keys: Vec<String>) {
+    if keys.is_empty() {
+      return;
+    }
+    self.google_api_key = Some(keys.remove(0));
+    self.google_api_keys = keys;
   }
 
-  pub fn get_google_api_key(&self) -> Option<String> {
-    self.google_api_key.clone()
+  pub fn get_google_api_keys(&self) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::new();
+    for key in self.google_api_key.iter().chain(&self.google_api_keys) {
+      if !key.is_empty() && !keys.contains(key) {
+        keys.push(key.clone());
+      }
+    }
+    keys
   }
 
-  fn config_path() -> Result<PathBuf, String> {
-    let home = dirs::home_dir().ok_or("Could not find home directory")?;
-    Ok(home.join(".wtf").join("config.json"))
+  /// Uncategorized typos are always active.
+  pub fn is_typo_active(&self, typo: &CustomTypo) -> bool {
+    match &typo.category {
+      Some(category) if !self.active_categories.is_empty() => self
+        .active_categories
+        .iter()
+        .any(|active| active.eq_ignore_ascii_case(category)),
+      _ => true,
+    }
+  }
+
+  /// Custom typos that duplicate, conflict with, or are hidden by other fixes, by index.
+  pub fn check_typos(&self) -> Vec<(usize, TypoIssue)> {
+    let builtins = get_common_fixes();
+
+    self
+      .custom_typos
+      .iter()
+      .enumerate()
+      .filter_map(|(i, typo)| {
+        let issue = if let Some(first) = self.custom_typos[..i]
+          .iter()
+          .position(|earlier| earlier.wrong == typo.wrong)
+        {
+          TypoIssue::Shadowed(first)
+        } else if typo.wrong == typo.correct && !typo.ignore_case {
+          TypoIssue::NoOp
+        } else if !self.is_typo_active(typo) {
+          TypoIssue::Inactive
+        } else {
+          let (_, (builtin, _, _)) = builtins.iter().find(|(wrong, _)| *wrong == typo.wrong)?;
+          if *builtin == typo.correct {
+            TypoIssue::Redundant
+          } else {
+            TypoIssue::Conflicts(builtin)
+          }
+        };
+        Some((i, issue))
+      })
+      .collect()
+  }
+
+  pub fn is_exec_allowed(&self, cmd: &str) -> bool {
+    if self.exec_allowlist.is_empty() {
+      return true;
+    }
+
+    match cmd.split_whitespace().next() {
+      Some(program) => self.exec_allowlist.iter().any(|allowed| allowed == program),
+      None => false,
+    }
+  }
+
+  /// Whether the fix starts with the words of a `safe_commands` entry. Anything with
+  /// pipes, redirects, or other shell syntax is never safe, whatever it starts with.
+  pub fn is_safe_command(&self, cmd: &str) -> bool {
+    if cmd.contains(['|', ';', '&', '>', '<', '$', '`', '(', ')']) {
+      return false;
+    }
+
+    let words: Vec<&str> = cmd.split_whitespace().collect();
+    self.safe_commands.iter().any(|safe| {
+      let safe_words: Vec<&str> = safe.split_whitespace().collect();
+      !safe_words.is_empty() && words.starts_with(&safe_words)
+    })
+  }
+
+  /// Parses the config file strictly: unlike `try_load`, a missing file is an error too.
+  pub fn validate_file() -> Result<UserConfig, WtfError> {
+    Self::parse_file(&Self::config_path()?)
+  }
+
+  /// Settings that parse but can't work as written, grouped for `wtf config check`.
+  pub fn check(&self) -> Vec<ConfigCheck> {
+    let mut typos = Vec::new();
+    for typo in &self.custom_typos {
+      if typo.wrong.trim().is_empty() || typo.correct.trim().is_empty() {
+        typos.push(format!(
+          "'{}' -> '{}' has an empty side",
+          typo.wrong, typo.correct
+        ));
+      }
+      if typo.match_mode == MatchMode::Word && has_placeholders(&typo.correct) {
+        typos.push(format!(
+          "'{}' is a --word fix, which can't use {{args}} or {{N}}",
+          typo.wrong
+        ));
+      }
+    }
+    for (i, issue) in self.check_typos() {
+      let wrong = &self.custom_typos[i].wrong;
+      match issue {
+        TypoIssue::NoOp => typos.push(format!("'{}' is fixed to itself", wrong)),
+        TypoIssue::Shadowed(_) => typos.push(format!("'{}' is defined more than once", wrong)),
+        _ => {}
+      }
+    }
+
+    let mut thresholds = Vec::new();
+    if !(0.0..=1.0).contains(&self.auto_confidence_threshold) {
+      thresholds.push(format!(
+        "auto_confidence_threshold is {}, expected 0.0 to 1.0",
+        self.auto_confidence_threshold
+      ));
+    }
+
+    let mut ai = Vec::new();
+    let fallback = match &self.ai_fallback_provider {
+      Some(name) => {
+        let provider = AiProvider::from_name(name);
+        if provider.is_none() {
+          ai.push(format!(
+            "Unknown ai_fallback_provider '{}' (expected gemini or ollama)",
+            name
+          ));
+        }
+        provider
+      }
+      None => None,
+    };
+    if !(1..=MAX_AI_CANDIDATES).contains(&self.ai_candidate_count) {
+      ai.push(format!(
+        "ai_candidate_count is {}, expected 1 to {}",
+        self.ai_candidate_count, MAX_AI_CANDIDATES
+      ));
+    }
+    if let Some(url) = &self.ollama_url {
+      if !url.starts_with("http://") && !url.starts_with("https://") {
+        ai.push(format!(
+          "ollama_url '{}' should start with http:// or https://",
+          url
+        ));
+      }
+    }
+    let has_key = !self.get_google_api_keys().is_empty()
+      || env::var("GOOGLE_API_KEY").is_ok_and(|key| !key.is_empty());
+    if self.ai_mode && !has_key && fallback != Some(AiProvider::Ollama) {
+      ai.push(
+        "ai_mode is on but there is no Google API key (or GOOGLE_API_KEY) and no Ollama fallback"
+          .to_string(),
+      );
+    }
+
+    let shell_names: Vec<&str> = ShellType::NAMES.iter().map(|(name, _)| *name).collect();
+    let history = self
+      .history_sources
+      .iter()
+      .filter(|source| ShellType::from_name(source).is_none())
+      .map(|source| {
+        format!(
+          "Unknown history source '{}' (expected one of {})",
+          source,
+          shell_names.join(", ")
+        )
+      })
+      .collect();
+
+    let categories = self
+      .active_categories
+      .iter()
+      .filter(|category| {
+        !self
+          .custom_typos
+          .iter()
+          .any(|typo| typo.category.as_ref() == Some(*category))
+      })
+      .map(|category| format!("No custom typo is in active category '{}'", category))
+      .collect();
+
+    let allowlist = if self.exec_allowlist.iter().any(|p| p.trim().is_empty()) {
+      vec!["exec_allowlist has an empty entry".to_string()]
+    } else {
+      Vec::new()
+    };
+
+    let safe = if self.safe_commands.iter().any(|c| c.trim().is_empty()) {
+      vec!["safe_commands has an empty entry".to_string()]
+    } else {
+      Vec::new()
+    };
+
+    vec![
+      ConfigCheck::new("Custom typos", typos),
+      ConfigCheck::new("Auto-run threshold", thresholds),
+      ConfigCheck::new("AI providers", ai),
+      ConfigCheck::new("History sources", history),
+      ConfigCheck::new("Active categories", categories),
+      ConfigCheck::new("Exec allowlist", allowlist),
+      ConfigCheck::new("Safe commands", safe),
+    ]
+  }
+
+  /// Uses a config file other than ~/.wtf/config.json for the rest of the run.
+  pub fn set_path_override(path: PathBuf) {
+    let _ = CONFIG_OVERRIDE.set(path);
+  }
+
+  pub fn config_path() -> Result<PathBuf, WtfError> {
+    if let Some(path) = CONFIG_OVERRIDE.get() {
+      return Ok(path.clone());
+    }
+
+    if let Some(path) = env::var_os("WTF_CONFIG").filter(|p| !p.is_empty()) {
+      return Ok(PathBuf::from(path));
+    }
+
+    Ok(wtf_dir()?.join("config.json"))
   }
 
   pub fn get_config_path_display() -> String {
@@ -119,3 +619,110 @@ impl UserConfig {
       .unwrap_or_else(|_| "unknown".to_string())
   }
 }
+
+/// The directory that holds `.wtf` and the shell history files. `WTF_HOME` replaces
+/// it for sandboxes and containers where `$HOME` is missing or read-only.
+pub fn home_dir() -> Result<PathBuf, WtfError> {
+  if let Some(path) = env::var_os("WTF_HOME").filter(|p| !p.is_empty()) {
+    return Ok(PathBuf::from(path));
+  }
+
+  dirs::home_dir().ok_or(WtfError::HomeDirMissing)
+}
+
+fn profiles_dir() -> Result<PathBuf, WtfError> {
+  Ok(wtf_dir()?.join("profiles"))
+}
+
+/// `~/.wtf/profiles/<name>/config.json`. Names are single path components.
+pub fn profile_config_path(name: &str) -> Result<PathBuf, WtfError> {
+  let valid = !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\']);
+  if !valid {
+    return Err(WtfError::ConfigError(format!(
+      "Invalid profile name: '{}'",
+      name
+    )));
+  }
+
+  Ok(profiles_dir()?.join(name).join("config.json"))
+}
+
+/// Names of the profiles that have a config file, sorted.
+pub fn list_profiles() -> Result<Vec<String>, WtfError> {
+  let dir = profiles_dir()?;
+  if !dir.exists() {
+    return Ok(Vec::new());
+  }
+
+  let entries = fs::read_dir(&dir)
+    .map_err(|e| WtfError::ConfigError(format!("Failed to read {}: {}", dir.display(), e)))?;
+
+  let mut names: Vec<String> = entries
+    .filter_map(|entry| entry.ok())
+    .filter(|entry| entry.path().join("config.json").is_file())
+    .filter_map(|entry| entry.file_name().into_string().ok())
+    .collect();
+  names.sort();
+  Ok(names)
+}
+
+/// Creates a profile with default settings and returns its config path.
+pub fn create_profile(name: &str) -> Result<PathBuf, WtfError> {
+  let path = profile_config_path(name)?;
+  if path.exists() {
+    return Err(WtfError::ConfigError(format!(
+      "Profile '{}' already exists",
+      name
+    )));
+  }
+
+  // Installing and the first-run prompt are about the binary, not a profile
+  let config = UserConfig {
+    first_run_complete: true,
+    ..UserConfig::default()
+  };
+  config.save_to(&path)?;
+  Ok(path)
+}
+
+pub fn wtf_dir() -> Result<PathBuf, WtfError> {
+  Ok(home_dir()?.join(".wtf"))
+}
+
+fn last_fix_path() -> Result<PathBuf, WtfError> {
+  Ok(wtf_dir()?.join("last_fix"))
+}
+
+pub fn save_last_fix(cmd: &str) -> Result<(), WtfError> {
+  let path = last_fix_path()?;
+
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)
+      .map_err(|e| WtfError::ConfigError(format!("Failed to create config directory: {}", e)))?;
+  }
+
+  fs::write(&path, cmd)
+    .map_err(|e| WtfError::ConfigError(format!("Failed to write last fix: {}", e)))
+}
+
+pub fn load_last_fix() -> Result<String, WtfError> {
+  let path = last_fix_path()?;
+
+  if !path.exists() {
+    return Err(WtfError::NoCorrections(
+      "No fix has been run yet. Run 'wtf' to fix a command first.".to_string(),
+    ));
+  }
+
+  let content = fs::read_to_string(&path)
+    .map_err(|e| WtfError::ConfigError(format!("Failed to read last fix: {}", e)))?;
+  let cmd = content.trim();
+
+  if cmd.is_empty() {
+    return Err(WtfError::NoCorrections(
+      "No fix has been run yet. Run 'wtf' to fix a command first.".to_string(),
+    ));
+  }
+
+  Ok(cmd.to_string())
+}
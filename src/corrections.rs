@@ -1,15 +1,115 @@
-use crate::commands::{get_common_commands, get_common_fixes};
-use crate::config::UserConfig;
-use strsim::jaro_winkler;
+use crate::commands::{get_common_commands, get_common_fixes, get_subcommands};
+use crate::config::{MatchMode, UserConfig};
+use crate::learned::learned_fix;
+use crate::parse::{find_substitutions, split_chain, substitutions_balanced};
+use crate::path::command_exists;
+use crate::rules::{apply_rules, is_trash_command, trash_alternative};
+use crate::stats::AcceptanceStats;
+use regex::Regex;
+use std::sync::OnceLock;
+use strsim::{damerau_levenshtein, jaro_winkler, normalized_levenshtein};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CorrectionSource {
+  Custom,
+  Learned,
+  Builtin,
+  Rule,
+  Fuzzy,
+}
+
+impl CorrectionSource {
+  pub fn name(&self) -> &'static str {
+    match self {
+      CorrectionSource::Custom => "custom",
+      CorrectionSource::Learned => "learned",
+      CorrectionSource::Builtin => "builtin",
+      CorrectionSource::Rule => "rule",
+      CorrectionSource::Fuzzy => "fuzzy",
+    }
+  }
+}
 
 #[derive(Debug, Clone)]
 pub struct Correction {
   pub fixed_cmd: String,
   pub reason: String,
   pub confidence: f64,
+  pub source: CorrectionSource,
+}
+
+// Anything longer is almost certainly an accidental paste, not a typo.
+pub const MAX_INPUT_CHARS: usize = 1000;
+
+pub fn find_corrections(
+  cmd: &str,
+  user_config: &UserConfig,
+  stats: &AcceptanceStats,
+) -> Option<Vec<Correction>> {
+  if cmd.chars().count() > MAX_INPUT_CHARS {
+    return None;
+  }
+
+  if let Some((command, comment)) = split_comment(cmd) {
+    let mut corrections = find_corrections(command, user_config, stats)?;
+    for correction in &mut corrections {
+      correction.fixed_cmd = format!("{} {}", correction.fixed_cmd.trim_end(), comment);
+    }
+    return Some(corrections);
+  }
+
+  if user_config.strip_background {
+    if let Some(foreground) = strip_background(cmd) {
+      let mut corrections = find_corrections(foreground, user_config, stats)?;
+      for correction in &mut corrections {
+        correction.fixed_cmd = format!("{} &", correction.fixed_cmd.trim_end());
+      }
+      return Some(corrections);
+    }
+  }
+
+  if user_config.normalize_unicode {
+    let normalized = normalize_unicode(cmd);
+    if normalized != cmd {
+      return find_corrections_in(&normalized, user_config, stats).or_else(|| {
+        Some(vec![Correction {
+          fixed_cmd: normalized,
+          reason: "normalized quotes and spaces".to_string(),
+          confidence: 1.0,
+          source: CorrectionSource::Rule,
+        }])
+      });
+    }
+  }
+
+  let cleaned = clean_whitespace(cmd);
+  if cleaned != cmd {
+    let mut corrections = find_corrections_in(&cleaned, user_config, stats).unwrap_or_default();
+
+    // The cleaned command itself is only worth offering when it's a real command
+    let program = cleaned.split_whitespace().next().unwrap_or_default();
+    if command_exists(program) {
+      corrections.push(Correction {
+        fixed_cmd: cleaned,
+        reason: "whitespace cleanup".to_string(),
+        confidence: 0.9,
+        source: CorrectionSource::Rule,
+      });
+    }
+
+    let mut corrections = rank_corrections(cmd, corrections, stats);
+    corrections.truncate(5);
+    return (!corrections.is_empty()).then_some(corrections);
+  }
+
+  find_corrections_in(cmd, user_config, stats)
 }
 
-pub fn find_corrections(cmd: &str, user_config: &UserConfig) -> Option<Vec<Correction>> {
+fn find_corrections_in(
+  cmd: &str,
+  user_config: &UserConfig,
+  stats: &AcceptanceStats,
+) -> Option<Vec<Correction>> {
   let mut corrections = Vec::new();
 
   let parts: Vec<&str> = cmd.split_whitespace().collect();
@@ -24,12 +124,38 @@ pub fn find_corrections(cmd: &str, user_config: &UserConfig) -> Option<Vec<Corre
     String::new()
   };
 
-  for (wrong, correct) in &user_config.custom_typos {
+  for typo in &user_config.custom_typos {
+    if !user_config.is_typo_active(typo) {
+      continue;
+    }
+
+    let (wrong, correct) = (&typo.wrong, &typo.correct);
+    let same = |a: &str, b: &str| {
+      if typo.ignore_case {
+        a.eq_ignore_ascii_case(b)
+      } else {
+        a == b
+      }
+    };
+
+    if typo.match_mode == MatchMode::Word {
+      if let Some(fixed) = replace_words(cmd, wrong, correct, same) {
+        corrections.push(Correction {
+          fixed_cmd: fixed,
+          reason: "custom fix".to_string(),
+          confidence: 1.0,
+          source: CorrectionSource::Custom,
+        });
+      }
+      continue;
+    }
+
     // Exact match
-    if cmd == wrong || command == wrong {
-      let fixed = if cmd == wrong {
-        correct.clone()
-      } else if args.is_empty() {
+    let whole = same(cmd, wrong);
+    if whole || same(command, wrong) {
+      let fixed = if has_placeholders(correct) {
+        fill_placeholders(correct, if whole { "" } else { &args })
+      } else if whole || args.is_empty() {
         correct.clone()
       } else {
         format!("{} {}", correct, args)
@@ -39,15 +165,38 @@ pub fn find_corrections(cmd: &str, user_config: &UserConfig) -> Option<Vec<Corre
         fixed_cmd: fixed,
         reason: "custom fix".to_string(),
         confidence: 1.0,
+        source: CorrectionSource::Custom,
       });
-    } else if cmd.starts_with(wrong) && cmd.len() > wrong.len() {
+    } else if cmd
+      .get(..wrong.len())
+      .is_some_and(|start| same(start, wrong))
+      && cmd.len() > wrong.len()
+    {
       let remaining = &cmd[wrong.len()..];
       if remaining.starts_with(' ') {
-        let fixed = format!("{}{}", correct, remaining);
+        let fixed = if has_placeholders(correct) {
+          fill_placeholders(correct, remaining)
+        } else {
+          format!("{}{}", correct, remaining)
+        };
         corrections.push(Correction {
           fixed_cmd: fixed,
           reason: "custom fix".to_string(),
           confidence: 1.0,
+          source: CorrectionSource::Custom,
+        });
+      }
+    }
+  }
+
+  if user_config.remember_per_directory {
+    if let Some(fixed) = learned_fix(cmd) {
+      if !corrections.iter().any(|c| c.fixed_cmd == fixed) {
+        corrections.push(Correction {
+          fixed_cmd: fixed.to_string(),
+          reason: "fixed this way here before".to_string(),
+          confidence: 0.95,
+          source: CorrectionSource::Learned,
         });
       }
     }
@@ -67,8 +216,7 @@ pub fn find_corrections(cmd: &str, user_config: &UserConfig) -> Option<Vec<Corre
     if matched {
       let fixed = if cmd == *typo_pattern {
         fix_info.0.to_string()
-      } else if cmd.starts_with(typo_pattern) {
-        let remaining = &cmd[typo_pattern.len()..];
+      } else if let Some(remaining) = cmd.strip_prefix(typo_pattern) {
         format!("{}{}", fix_info.0, remaining)
       } else if args.is_empty() {
         fix_info.0.to_string()
@@ -80,12 +228,40 @@ pub fn find_corrections(cmd: &str, user_config: &UserConfig) -> Option<Vec<Corre
         corrections.push(Correction {
           fixed_cmd: fixed,
           reason: fix_info.1.to_string(),
-          confidence: 1.0,
+          confidence: fix_info.2.unwrap_or(1.0),
+          source: CorrectionSource::Builtin,
         });
       }
     }
   }
 
+  for correction in apply_rules(cmd, user_config) {
+    if !corrections
+      .iter()
+      .any(|c| c.fixed_cmd == correction.fixed_cmd)
+    {
+      corrections.push(correction);
+    }
+  }
+
+  for correction in correct_substitutions(cmd, user_config, stats) {
+    if !corrections
+      .iter()
+      .any(|c| c.fixed_cmd == correction.fixed_cmd)
+    {
+      corrections.push(correction);
+    }
+  }
+
+  if let Some(correction) = correct_chain(cmd, user_config, stats) {
+    if !corrections
+      .iter()
+      .any(|c| c.fixed_cmd == correction.fixed_cmd)
+    {
+      corrections.push(correction);
+    }
+  }
+
   if corrections.is_empty() {
     let common_commands = get_common_commands();
 
@@ -93,6 +269,27 @@ pub fn find_corrections(cmd: &str, user_config: &UserConfig) -> Option<Vec<Corre
       let similarity = jaro_winkler(command, common_cmd);
 
       if similarity > 0.85 && similarity < 1.0 {
+        // Both words are often mistyped together (`gut plul`), so try the subcommand too.
+        if let Some((subcommand, sub_similarity)) = parts
+          .get(1)
+          .and_then(|word| closest_subcommand(common_cmd, word, 0.85))
+        {
+          let rest = parts[2..].join(" ");
+          let fixed = if rest.is_empty() {
+            format!("{} {}", common_cmd, subcommand)
+          } else {
+            format!("{} {} {}", common_cmd, subcommand, rest)
+          };
+
+          corrections.push(Correction {
+            fixed_cmd: fixed,
+            reason: format!("similar to '{} {}'", common_cmd, subcommand),
+            confidence: similarity * sub_similarity,
+            source: CorrectionSource::Fuzzy,
+          });
+          continue;
+        }
+
         let fixed = if args.is_empty() {
           common_cmd.to_string()
         } else {
@@ -103,12 +300,35 @@ pub fn find_corrections(cmd: &str, user_config: &UserConfig) -> Option<Vec<Corre
           fixed_cmd: fixed,
           reason: format!("similar to '{}'", common_cmd),
           confidence: similarity,
+          source: CorrectionSource::Fuzzy,
         });
       }
     }
   }
 
-  corrections.sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap());
+  // Ranked just below the fix it's based on
+  let trash_alternatives: Vec<Correction> = corrections
+    .iter()
+    .filter_map(|c| trash_alternative(&c.fixed_cmd, c.confidence * 0.9))
+    .collect();
+  for correction in trash_alternatives {
+    if !corrections
+      .iter()
+      .any(|c| c.fixed_cmd == correction.fixed_cmd)
+    {
+      corrections.push(correction);
+    }
+  }
+
+  let normalized_cmd = normalize_whitespace(cmd);
+  corrections.retain(|c| normalize_whitespace(&c.fixed_cmd) != normalized_cmd);
+
+  // A fix must never leave a `$(` or backtick open that the user had closed.
+  if substitutions_balanced(cmd) {
+    corrections.retain(|c| substitutions_balanced(&c.fixed_cmd));
+  }
+
+  let mut corrections = rank_corrections(cmd, corrections, stats);
 
   if corrections.len() > 5 {
     corrections.truncate(5);
@@ -120,3 +340,732 @@ pub fn find_corrections(cmd: &str, user_config: &UserConfig) -> Option<Vec<Corre
     Some(corrections)
   }
 }
+
+const SUBSTITUTION_REASON: &str = "in substitution: ";
+
+// `echo $(gti rev-parse HEAD)` -> `echo $(git rev-parse HEAD)`: each substitution is
+// corrected as a command of its own (recursing into nested ones) and spliced back in.
+fn correct_substitutions(
+  cmd: &str,
+  user_config: &UserConfig,
+  stats: &AcceptanceStats,
+) -> Vec<Correction> {
+  let Some(subs) = find_substitutions(cmd) else {
+    return Vec::new();
+  };
+
+  subs
+    .into_iter()
+    .filter_map(|sub| {
+      let inner = &cmd[sub.start..sub.end];
+      let best = find_corrections_in(inner, user_config, stats)?
+        .into_iter()
+        .next()?;
+
+      Some(Correction {
+        fixed_cmd: format!("{}{}{}", &cmd[..sub.start], best.fixed_cmd, &cmd[sub.end..]),
+        reason: if best.reason.starts_with(SUBSTITUTION_REASON) {
+          best.reason
+        } else {
+          format!("{}{}", SUBSTITUTION_REASON, best.reason)
+        },
+        confidence: best.confidence,
+        source: best.source,
+      })
+    })
+    .collect()
+}
+
+const CHAIN_REASON: &str = "in chained command: ";
+
+// Lowest confidence a segment's fix needs before the whole chain is rewritten around it.
+const CHAIN_MIN_CONFIDENCE: f64 = 0.8;
+
+// `mkdir foo && gti add foo` -> `mkdir foo && git add foo`: each command of the chain is
+// corrected on its own. Only a chain with exactly one confident fix is rewritten, since
+// several guesses at once are unlikely to all be right.
+fn correct_chain(
+  cmd: &str,
+  user_config: &UserConfig,
+  stats: &AcceptanceStats,
+) -> Option<Correction> {
+  let segments = split_chain(cmd)?;
+
+  let mut fixes = segments.iter().filter_map(|segment| {
+    let text = &cmd[segment.start..segment.end];
+    let inner = text.trim();
+    if inner.is_empty() {
+      return None;
+    }
+
+    // `mkdir` in `mkdir foo && gti add foo` looks a lot like `rmdir`, but it's
+    // what the user meant
+    let program = inner.split_whitespace().next()?;
+    let best = find_corrections_in(inner, user_config, stats)?
+      .into_iter()
+      .next()
+      .filter(|best| best.confidence >= CHAIN_MIN_CONFIDENCE)
+      .filter(|best| best.source != CorrectionSource::Fuzzy || !is_known_command(program))?;
+
+    // Keep the spacing around the segment as typed
+    let start = segment.start + (text.len() - text.trim_start().len());
+    Some((start, start + inner.len(), best))
+  });
+
+  let (start, end, best) = fixes.next()?;
+  if fixes.next().is_some() {
+    return None;
+  }
+
+  Some(Correction {
+    fixed_cmd: format!("{}{}{}", &cmd[..start], best.fixed_cmd, &cmd[end..]),
+    reason: format!("{}{}", CHAIN_REASON, best.reason),
+    confidence: best.confidence,
+    source: best.source,
+  })
+}
+
+// On PATH, or a shell builtin like `cd` that never is.
+fn is_known_command(program: &str) -> bool {
+  command_exists(program) || get_common_commands().contains(&program)
+}
+
+fn placeholder_pattern() -> &'static Regex {
+  static PATTERN: OnceLock<Regex> = OnceLock::new();
+  PATTERN.get_or_init(|| Regex::new(r"\{(args|[1-9][0-9]*)\}").unwrap())
+}
+
+pub fn has_placeholders(correct: &str) -> bool {
+  placeholder_pattern().is_match(correct)
+}
+
+// `gco feature-x` with the fix `git checkout {args}` -> `git checkout feature-x`.
+// `{args}` is everything after the typo, `{1}`, `{2}`... single words of it (empty if missing).
+fn fill_placeholders(correct: &str, rest: &str) -> String {
+  let args: Vec<&str> = rest.split_whitespace().collect();
+
+  placeholder_pattern()
+    .replace_all(correct, |caps: &regex::Captures| match &caps[1] {
+      "args" => args.join(" "),
+      n => n
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| args.get(n - 1))
+        .map(|arg| arg.to_string())
+        .unwrap_or_default(),
+    })
+    .trim_end()
+    .to_string()
+}
+
+// Replaces every whole-word occurrence of `wrong` (one or more words) with `correct`.
+// `sudo sl -la` with `sl` -> `ls` becomes `sudo ls -la`, but `slack` is left alone.
+fn replace_words(
+  cmd: &str,
+  wrong: &str,
+  correct: &str,
+  same: impl Fn(&str, &str) -> bool,
+) -> Option<String> {
+  let wrong_words: Vec<&str> = wrong.split_whitespace().collect();
+  if wrong_words.is_empty() {
+    return None;
+  }
+
+  let words: Vec<&str> = cmd.split_whitespace().collect();
+  let mut fixed = Vec::with_capacity(words.len());
+  let mut replaced = false;
+  let mut i = 0;
+
+  while i < words.len() {
+    let matches = words.len() - i >= wrong_words.len()
+      && words[i..]
+        .iter()
+        .zip(&wrong_words)
+        .all(|(word, wrong)| same(word, wrong));
+    if matches {
+      fixed.push(correct);
+      i += wrong_words.len();
+      replaced = true;
+    } else {
+      fixed.push(words[i]);
+      i += 1;
+    }
+  }
+
+  replaced.then(|| fixed.join(" "))
+}
+
+// Returns the closest known subcommand of `tool` when `word` looks like a typo of one.
+// An exact subcommand, a flag, or an unknown tool all return None.
+fn closest_subcommand(tool: &str, word: &str, min_similarity: f64) -> Option<(&'static str, f64)> {
+  if word.starts_with('-') {
+    return None;
+  }
+
+  let subcommands = get_subcommands()
+    .into_iter()
+    .find(|(name, _)| *name == tool)?
+    .1;
+
+  if subcommands.contains(&word) {
+    return None;
+  }
+
+  subcommands
+    .into_iter()
+    .map(|sub| (sub, jaro_winkler(word, sub)))
+    .filter(|(_, similarity)| *similarity > min_similarity)
+    .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+// How loose the offline `--ai` guess may be; regular fuzzy matching wants 0.85.
+const HEURISTIC_SIMILARITY: f64 = 0.7;
+
+const HEURISTIC_REASON: &str = "loose match";
+
+/// One best guess for `--ai` when no AI provider is set up: the top regular correction
+/// (or failing that, a much looser match of the command), with a mistyped subcommand
+/// also matched loosely.
+pub fn heuristic_fix(
+  cmd: &str,
+  user_config: &UserConfig,
+  stats: &AcceptanceStats,
+) -> Option<Correction> {
+  let mut best = confident_fix(cmd, user_config, stats)
+    .or_else(|| loose_program_fix(cmd))
+    .unwrap_or_else(|| Correction {
+      fixed_cmd: normalize_whitespace(cmd),
+      reason: HEURISTIC_REASON.to_string(),
+      confidence: 1.0,
+      source: CorrectionSource::Fuzzy,
+    });
+
+  // Fixing the command can reveal a typo behind it (`kubctl gt` -> `kubectl gt` -> `kubectl get`)
+  for _ in 0..HEURISTIC_PASSES {
+    let Some(next) = confident_fix(&best.fixed_cmd, user_config, stats) else {
+      break;
+    };
+    best.fixed_cmd = next.fixed_cmd;
+    best.confidence *= next.confidence;
+    best.reason = HEURISTIC_REASON.to_string();
+  }
+
+  if let Some((fixed_cmd, similarity)) = loose_subcommand_fix(&best.fixed_cmd) {
+    best.fixed_cmd = fixed_cmd;
+    best.confidence *= similarity;
+    best.reason = HEURISTIC_REASON.to_string();
+    best.source = CorrectionSource::Fuzzy;
+  }
+
+  (best.fixed_cmd != normalize_whitespace(cmd)).then_some(best)
+}
+
+const HEURISTIC_PASSES: usize = 2;
+
+// The top regular correction, unless it's a fuzzy guess replacing a command that exists
+fn confident_fix(
+  cmd: &str,
+  user_config: &UserConfig,
+  stats: &AcceptanceStats,
+) -> Option<Correction> {
+  let program = cmd.split_whitespace().next()?;
+  find_corrections(cmd, user_config, stats)?
+    .into_iter()
+    .next()
+    .filter(|best| best.source != CorrectionSource::Fuzzy || !is_known_command(program))
+}
+
+fn loose_program_fix(cmd: &str) -> Option<Correction> {
+  let (command, rest) = cmd
+    .trim()
+    .split_once(char::is_whitespace)
+    .unwrap_or((cmd.trim(), ""));
+  if command.is_empty() || is_known_command(command) {
+    return None;
+  }
+
+  let (program, similarity) = get_common_commands()
+    .into_iter()
+    .map(|common| (common, jaro_winkler(command, common)))
+    .filter(|(_, similarity)| *similarity > HEURISTIC_SIMILARITY)
+    .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+  Some(Correction {
+    fixed_cmd: normalize_whitespace(&format!("{} {}", program, rest)),
+    reason: HEURISTIC_REASON.to_string(),
+    confidence: similarity,
+    source: CorrectionSource::Fuzzy,
+  })
+}
+
+fn loose_subcommand_fix(cmd: &str) -> Option<(String, f64)> {
+  let mut parts: Vec<&str> = cmd.split_whitespace().collect();
+  let (subcommand, similarity) =
+    closest_subcommand(parts.first()?, parts.get(1)?, HEURISTIC_SIMILARITY)?;
+  parts[1] = subcommand;
+  Some((parts.join(" "), similarity))
+}
+
+// `longcmd &` -> `longcmd`. Leaves `&&`, `|&`, `>&` and `\&` alone: those aren't backgrounding.
+fn strip_background(cmd: &str) -> Option<&str> {
+  let foreground = cmd.trim_end().strip_suffix('&')?;
+  if foreground.ends_with(['&', '|', '>', '\\']) || foreground.trim().is_empty() {
+    return None;
+  }
+  Some(foreground.trim_end())
+}
+
+// `gti status # note` -> (`gti status`, `# note`). Like the shell, only an unquoted `#`
+// starting a word opens a comment, so `echo "#1"`, `a#b` and `${#var}` are left alone.
+fn split_comment(cmd: &str) -> Option<(&str, &str)> {
+  let mut quote = None;
+  let mut escaped = false;
+  let mut previous = ' ';
+
+  for (i, c) in cmd.char_indices() {
+    match quote {
+      _ if escaped => escaped = false,
+      Some('\'') if c == '\'' => quote = None,
+      Some('\'') => {}
+      _ if c == '\\' => escaped = true,
+      Some(_) if c == '"' => quote = None,
+      Some(_) => {}
+      None if c == '\'' || c == '"' => quote = Some(c),
+      None if c == '#' && previous.is_whitespace() => {
+        let command = cmd[..i].trim_end();
+        // A line that is all comment has nothing to correct
+        return (!command.is_empty()).then_some((command, &cmd[i..]));
+      }
+      None => {}
+    }
+    previous = c;
+  }
+  None
+}
+
+// Smart quotes and exotic spaces usually come from pasting out of chat apps or docs.
+pub fn normalize_unicode(cmd: &str) -> String {
+  cmd
+    .chars()
+    .filter_map(|c| match c {
+      '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' => Some('"'),
+      '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' => Some('\''),
+      '\u{00A0}' | '\u{2007}' | '\u{202F}' => Some(' '),
+      '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}' => None,
+      _ => Some(c),
+    })
+    .collect()
+}
+
+// Terminals wrap pasted text in ESC[200~ ... ESC[201~, and a paste can carry other escape
+// sequences or control bytes along. None of it belongs in a command, and echoing it back
+// would let it drive the terminal, so it is dropped. Tabs and line breaks are left as
+// whitespace for clean_whitespace.
+pub fn sanitize_command(cmd: &str) -> String {
+  let mut sanitized = String::with_capacity(cmd.len());
+  let mut chars = cmd.chars().peekable();
+
+  while let Some(c) = chars.next() {
+    if c == '\u{1b}' {
+      match chars.next() {
+        // CSI, e.g. bracketed paste markers and colors: ends at a byte in @..~
+        Some('[') => {
+          for c in chars.by_ref() {
+            if ('@'..='~').contains(&c) {
+              break;
+            }
+          }
+        }
+        // OSC, e.g. window titles: ends at BEL or ESC \
+        Some(']') => {
+          while let Some(c) = chars.next() {
+            if c == '\u{7}' {
+              break;
+            }
+            if c == '\u{1b}' && chars.peek() == Some(&'\\') {
+              chars.next();
+              break;
+            }
+          }
+        }
+        _ => {}
+      }
+    } else if c == '\n' || c == '\r' {
+      // Gluing `ls\n-la` into `ls-la` would make a new word out of two
+      sanitized.push(' ');
+    } else if c == '\t' || !c.is_control() {
+      sanitized.push(c);
+    }
+  }
+  sanitized
+}
+
+// Trims the command and turns runs of tabs/spaces into one space, leaving quoted text alone.
+// Pasting from an editor often brings a leading tab or trailing spaces along.
+fn clean_whitespace(cmd: &str) -> String {
+  let mut cleaned = String::with_capacity(cmd.len());
+  let mut quote = None;
+  let mut pending_space = false;
+
+  for c in cmd.trim().chars() {
+    match quote {
+      Some(q) if c == q => quote = None,
+      Some(_) => {}
+      None if c == ' ' || c == '\t' => {
+        pending_space = true;
+        continue;
+      }
+      None if c == '"' || c == '\'' => quote = Some(c),
+      None => {}
+    }
+
+    if pending_space {
+      cleaned.push(' ');
+      pending_space = false;
+    }
+    cleaned.push(c);
+  }
+
+  cleaned
+}
+
+fn normalize_whitespace(cmd: &str) -> String {
+  cmd.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Ranking weights. They sum to 1.0 so the final score stays in the 0..1 range.
+//
+// - CONFIDENCE: how sure the rule that produced the fix is
+// - SOURCE: how much we trust that kind of rule (see `source_priority`)
+// - SIMILARITY: how close the fix is to what was typed; real typos are small edits
+// - ACCEPTANCE: how often this fix was picked when it was offered before
+const CONFIDENCE_WEIGHT: f64 = 0.5;
+const SOURCE_WEIGHT: f64 = 0.2;
+const SIMILARITY_WEIGHT: f64 = 0.15;
+const ACCEPTANCE_WEIGHT: f64 = 0.15;
+
+// Used when a fix has never been offered before.
+const NEUTRAL_ACCEPTANCE: f64 = 0.5;
+
+// Added per extra rule that independently produced the same fix.
+const AGREEMENT_BONUS: f64 = 0.05;
+
+fn source_priority(source: CorrectionSource) -> f64 {
+  match source {
+    CorrectionSource::Custom => 1.0,
+    CorrectionSource::Learned => 0.95,
+    CorrectionSource::Builtin => 0.9,
+    CorrectionSource::Rule => 0.8,
+    CorrectionSource::Fuzzy => 0.6,
+  }
+}
+
+/// Merges duplicate fixes and orders the rest by a blended score, best first.
+pub fn rank_corrections(
+  cmd: &str,
+  corrections: Vec<Correction>,
+  stats: &AcceptanceStats,
+) -> Vec<Correction> {
+  let mut scored: Vec<(Correction, f64)> = Vec::new();
+
+  for correction in corrections {
+    let score = CONFIDENCE_WEIGHT * correction.confidence
+      + SOURCE_WEIGHT * source_priority(correction.source)
+      + SIMILARITY_WEIGHT * normalized_levenshtein(cmd, &correction.fixed_cmd)
+      + ACCEPTANCE_WEIGHT
+        * stats
+          .acceptance_rate(&correction.fixed_cmd)
+          .unwrap_or(NEUTRAL_ACCEPTANCE);
+
+    match scored
+      .iter_mut()
+      .find(|(existing, _)| existing.fixed_cmd == correction.fixed_cmd)
+    {
+      Some((existing, existing_score)) => {
+        if score > *existing_score {
+          *existing = correction;
+        }
+        *existing_score = existing_score.max(score) + AGREEMENT_BONUS;
+      }
+      None => scored.push((correction, score)),
+    }
+  }
+
+  scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+  scored
+    .into_iter()
+    .map(|(correction, _)| correction)
+    .collect()
+}
+
+// The original words in a gap left over once each fixed word has claimed something:
+// neighbours it joins exactly (`docker compose` -> `docker-compose` or `dockercompose`),
+// otherwise one word it's a change of. A word is never merged by merely being part of
+// a longer one, or `-r` would vanish into `--recursive`.
+fn unpaired_words<'a>(original: &[&'a str], fixed: &[&str]) -> Vec<&'a str> {
+  let mut merged = vec![false; original.len()];
+  let mut changes = 0;
+
+  for word in fixed {
+    let window = (0..original.len()).find_map(|start| {
+      (start + 2..=original.len())
+        .find(|&end| {
+          let words = &original[start..end];
+          !merged[start..end].contains(&true)
+            && (words.join("-") == *word || words.concat() == *word)
+        })
+        .map(|end| start..end)
+    });
+
+    match window {
+      Some(range) => merged[range].fill(true),
+      None => changes += 1,
+    }
+  }
+
+  original
+    .iter()
+    .zip(&merged)
+    .filter(|(_, merged)| !**merged)
+    .map(|(word, _)| *word)
+    .skip(changes)
+    .collect()
+}
+
+/// Words of `cmd` that `fixed` leaves out. Changing a word is a correction;
+/// removing one can quietly change what the command does.
+pub fn dropped_words<'a>(cmd: &'a str, fixed_cmd: &str) -> Vec<&'a str> {
+  let original: Vec<&str> = cmd.split_whitespace().collect();
+  let fixed: Vec<&str> = fixed_cmd.split_whitespace().collect();
+
+  // Longest common subsequence of words; between two matched words, an original word
+  // pairs up with a fixed one (a change) until the fixed side runs out (a removal).
+  let (n, m) = (original.len(), fixed.len());
+  let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+  for i in (0..n).rev() {
+    for j in (0..m).rev() {
+      lcs[i][j] = if original[i] == fixed[j] {
+        lcs[i + 1][j + 1] + 1
+      } else {
+        lcs[i + 1][j].max(lcs[i][j + 1])
+      };
+    }
+  }
+
+  let mut dropped = Vec::new();
+  let (mut i, mut j) = (0, 0);
+  let (mut gap_original, mut gap_fixed): (Vec<&str>, Vec<&str>) = (Vec::new(), Vec::new());
+
+  loop {
+    let matched = i < n && j < m && original[i] == fixed[j];
+    if matched || i == n && j == m {
+      dropped.extend(unpaired_words(&gap_original, &gap_fixed));
+      gap_original.clear();
+      gap_fixed.clear();
+      if !matched {
+        break;
+      }
+      i += 1;
+      j += 1;
+    } else if j == m || (i < n && lcs[i + 1][j] >= lcs[i][j + 1]) {
+      gap_original.push(original[i]);
+      i += 1;
+    } else {
+      gap_fixed.push(fixed[j]);
+      j += 1;
+    }
+  }
+
+  // A word that only moved (`commit git` -> `git commit`) is still there
+  let count = |words: &[&str], word: &str| words.iter().filter(|w| **w == word).count();
+  let mut kept = Vec::new();
+  for word in dropped {
+    if count(&kept, word) + count(&fixed, word) < count(&original, word) {
+      kept.push(word);
+    }
+  }
+
+  // `rm -rf x` -> `trash x`: the trash tool has no use for rm's flags, and nothing is lost
+  if is_trash_command(fixed_cmd) {
+    kept.retain(|word| !word.starts_with('-'));
+  }
+  kept
+}
+
+// A fuzzy match only replaces the command name and maybe its subcommand, so only
+// the leading words up to the last one that changed get learned.
+pub fn learnable_fix(cmd: &str, correction: &Correction) -> Option<(String, String)> {
+  if correction.source != CorrectionSource::Fuzzy {
+    return None;
+  }
+
+  let wrong: Vec<&str> = cmd.split_whitespace().collect();
+  let correct: Vec<&str> = correction.fixed_cmd.split_whitespace().collect();
+
+  let changed = wrong.iter().zip(&correct).rposition(|(w, c)| w != c)? + 1;
+
+  Some((wrong[..changed].join(" "), correct[..changed].join(" ")))
+}
+
+// Most edits (swaps count as one) between a typed word and its retry; short words get one.
+fn retry_max_edits(word: &str) -> usize {
+  if word.chars().count() <= 4 {
+    1
+  } else {
+    2
+  }
+}
+
+// `gti status` followed by `git status` -> ("gti", "git"). History has no exit codes,
+// so a retry only counts when the changed words weren't valid in the first place:
+// `git pull` then `git push` is two real commands, not a typo.
+pub fn retried_typo(typo: &str, retry: &str) -> Option<(String, String)> {
+  let wrong: Vec<&str> = typo.split_whitespace().collect();
+  let correct: Vec<&str> = retry.split_whitespace().collect();
+  if wrong.len() != correct.len() || wrong == correct {
+    return None;
+  }
+
+  let changed = wrong.iter().zip(&correct).rposition(|(w, c)| w != c)? + 1;
+  // Only the command name and its subcommand, as with a fuzzy match
+  if changed > 2 || !is_known_command(correct[0]) {
+    return None;
+  }
+
+  for (i, (w, c)) in wrong[..changed].iter().zip(&correct[..changed]).enumerate() {
+    if w == c {
+      continue;
+    }
+    if damerau_levenshtein(w, c) > retry_max_edits(c) {
+      return None;
+    }
+    let valid = if i == 0 {
+      is_known_command(w)
+    } else {
+      get_subcommands()
+        .into_iter()
+        .find(|(name, _)| *name == correct[0])
+        .is_none_or(|(_, subcommands)| subcommands.contains(w))
+    };
+    if valid {
+      return None;
+    }
+  }
+
+  Some((wrong[..changed].join(" "), correct[..changed].join(" ")))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn dropped_words_keeps_merged_neighbours() {
+    assert!(dropped_words("docker compose up", "docker-compose up").is_empty());
+    assert!(dropped_words("git sta tus", "git status").is_empty());
+  }
+
+  #[test]
+  fn dropped_words_ignores_moved_words() {
+    assert!(dropped_words("commit git -m x", "git commit -m x").is_empty());
+    assert_eq!(dropped_words("a b a", "b a"), vec!["a"]);
+  }
+
+  #[test]
+  fn dropped_words_reports_words_inside_longer_ones() {
+    assert_eq!(dropped_words("rm -f b", "rm -fab"), vec!["b"]);
+    assert_eq!(dropped_words("cp -r a b", "cp a b"), vec!["-r"]);
+  }
+
+  #[test]
+  fn normalize_unicode_straightens_smart_quotes() {
+    assert_eq!(
+      normalize_unicode("git commit -m \u{201C}fix\u{201D}"),
+      "git commit -m \"fix\""
+    );
+    assert_eq!(normalize_unicode("echo \u{2018}hi\u{2019}"), "echo 'hi'");
+  }
+
+  #[test]
+  fn normalize_unicode_turns_nbsp_into_spaces() {
+    assert_eq!(normalize_unicode("git\u{00A0}status"), "git status");
+    assert_eq!(normalize_unicode("ls\u{202F}-la"), "ls -la");
+  }
+
+  #[test]
+  fn normalize_unicode_drops_zero_width_characters() {
+    assert_eq!(
+      normalize_unicode("\u{FEFF}git st\u{200B}atus"),
+      "git status"
+    );
+    assert_eq!(normalize_unicode("ca\u{200D}t file"), "cat file");
+  }
+
+  #[test]
+  fn correct_substitutions_fixes_nested_commands() {
+    let config = UserConfig::default();
+    let stats = AcceptanceStats::default();
+    let fixes = correct_substitutions(
+      "echo $(basename $(gti rev-parse --show-toplevel))",
+      &config,
+      &stats,
+    );
+    assert_eq!(
+      fixes.first().map(|fix| fix.fixed_cmd.as_str()),
+      Some("echo $(basename $(git rev-parse --show-toplevel))")
+    );
+  }
+
+  #[test]
+  fn correct_substitutions_leaves_unbalanced_input_alone() {
+    let config = UserConfig::default();
+    let stats = AcceptanceStats::default();
+    assert!(correct_substitutions("echo $(gti status", &config, &stats).is_empty());
+    assert!(correct_substitutions("echo `gti status", &config, &stats).is_empty());
+  }
+
+  #[test]
+  fn strip_background_drops_a_trailing_ampersand() {
+    assert_eq!(strip_background("longcmd &"), Some("longcmd"));
+    assert_eq!(strip_background("a && b &"), Some("a && b"));
+    assert_eq!(strip_background("sleep 10&  "), Some("sleep 10"));
+  }
+
+  #[test]
+  fn strip_background_leaves_other_ampersands_alone() {
+    assert_eq!(strip_background("a && b"), None);
+    assert_eq!(strip_background("make 2>&"), None);
+    assert_eq!(strip_background("echo \"&\""), None);
+    assert_eq!(strip_background("echo '&'"), None);
+    assert_eq!(strip_background("echo \\&"), None);
+    assert_eq!(strip_background("&"), None);
+  }
+
+  #[test]
+  fn split_comment_splits_off_a_trailing_comment() {
+    assert_eq!(
+      split_comment("gti status # note"),
+      Some(("gti status", "# note"))
+    );
+    assert_eq!(split_comment("# just a comment"), None);
+  }
+
+  #[test]
+  fn split_comment_ignores_hashes_that_are_not_comments() {
+    assert_eq!(split_comment("echo \"#1\""), None);
+    assert_eq!(split_comment("echo '# not this'"), None);
+    assert_eq!(split_comment("echo a#b"), None);
+    assert_eq!(split_comment("echo ${#var}"), None);
+    assert_eq!(split_comment("echo \\# literal"), None);
+  }
+
+  #[test]
+  fn sanitize_command_turns_line_breaks_into_spaces() {
+    assert_eq!(sanitize_command("ls\n-la"), "ls -la");
+    assert_eq!(sanitize_command("git status\r\n"), "git status  ");
+    assert_eq!(
+      sanitize_command("\u{1b}[200~gti\tstatus\u{1b}[201~"),
+      "gti\tstatus"
+    );
+  }
+}
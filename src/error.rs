@@ -0,0 +1,41 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum WtfError {
+  /// No usable history file, or no valid command in it
+  HistoryNotFound(String),
+  /// The history file exists but couldn't be read
+  HistoryUnreadable(String),
+  /// The most recent command is older than the `--since` window
+  HistoryStale(String),
+  /// Nothing to run: no suggestions for the command, or no fix recorded yet
+  NoCorrections(String),
+  /// No Google API key in the environment or config
+  ApiKeyMissing,
+  /// The AI provider couldn't be reached or gave an unusable answer
+  AiRequestFailed(String),
+  /// The config (or another file under ~/.wtf) couldn't be read, parsed, or written
+  ConfigError(String),
+  /// Neither WTF_HOME nor a home directory is available
+  HomeDirMissing,
+}
+
+impl fmt::Display for WtfError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      WtfError::HistoryNotFound(msg)
+      | WtfError::HistoryUnreadable(msg)
+      | WtfError::HistoryStale(msg)
+      | WtfError::NoCorrections(msg)
+      | WtfError::AiRequestFailed(msg)
+      | WtfError::ConfigError(msg) => write!(f, "{}", msg),
+      WtfError::ApiKeyMissing => write!(f, "Google API key not found"),
+      WtfError::HomeDirMissing => write!(
+        f,
+        "Could not find your home directory. Set HOME, or set WTF_HOME to the directory wtf should use instead"
+      ),
+    }
+  }
+}
+
+impl std::error::Error for WtfError {}
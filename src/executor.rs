@@ -1,24 +1,160 @@
+use crate::commands::{get_dangerous_patterns, get_interactive_args, get_interactive_commands};
+use crate::interrupt::ChildGuard;
+use crate::logging;
+use crate::ui::display_debug;
+use regex::Regex;
 use std::process::{Command, Stdio};
+use std::sync::OnceLock;
 
-pub fn execute_command(cmd: &str) -> Result<(), String> {
-  let (shell, shell_arg) = if cfg!(target_os = "windows") {
-    ("powershell", "-Command")
+/// Runs the command in the platform shell and returns its exit code.
+/// Only failing to start the shell is an error; a non-zero exit is the caller's call.
+/// With `debug`, prints the exact program and arguments first, to make quoting visible.
+pub fn execute_command(cmd: &str, debug: bool) -> Result<i32, String> {
+  let interactive = needs_terminal(cmd);
+  let mut command = shell_command(cmd, interactive);
+
+  if debug {
+    if interactive {
+      display_debug("Interactive command, attaching it to the terminal");
+    }
+    display_debug(&format!("Executing: {:?}", command));
+  }
+
+  let (stdin, stdout) = if interactive {
+    terminal_stdio()
   } else {
-    ("sh", "-c")
+    (Stdio::inherit(), Stdio::inherit())
   };
 
-  let status = Command::new(shell)
-    .arg(shell_arg)
-    .arg(cmd)
-    .stdin(Stdio::inherit())
-    .stdout(Stdio::inherit())
+  logging::log(&format!("executing: {:?}", command));
+  let _child = ChildGuard::new();
+  let status = command
+    .stdin(stdin)
+    .stdout(stdout)
     .stderr(Stdio::inherit())
     .status()
     .map_err(|e| format!("Failed to execute command: {}", e))?;
 
-  if !status.success() {
-    return Err(format!("Command exited with status: {}", status));
+  let code = exit_code(status);
+  logging::log(&format!("exit code: {}", code));
+  Ok(code)
+}
+
+/// True for editors, pagers, and the like (`sudo vim file`, `git rebase -i`), which
+/// only work when they own the terminal.
+fn needs_terminal(cmd: &str) -> bool {
+  let mut words = cmd
+    .split_whitespace()
+    .skip_while(|word| matches!(*word, "sudo" | "doas" | "env") || is_env_assignment(word));
+
+  let Some(program) = words.next() else {
+    return false;
+  };
+  let program = program.rsplit(['/', '\\']).next().unwrap_or(program);
+  let program = program.strip_suffix(".exe").unwrap_or(program);
+
+  if get_interactive_commands().contains(&program) {
+    return true;
   }
 
-  Ok(())
+  let args: Vec<&str> = words.collect();
+  get_interactive_args()
+    .iter()
+    .any(|(name, triggers)| *name == program && args.iter().any(|arg| triggers.contains(arg)))
+}
+
+/// What makes the command hard to undo (`rm -rf`, `git push --force`), if anything.
+pub fn danger_reason(cmd: &str) -> Option<&'static str> {
+  static PATTERNS: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+
+  PATTERNS
+    .get_or_init(|| {
+      get_dangerous_patterns()
+        .into_iter()
+        .filter_map(|(pattern, reason)| Some((Regex::new(pattern).ok()?, reason)))
+        .collect()
+    })
+    .iter()
+    .find(|(re, _)| re.is_match(cmd))
+    .map(|(_, reason)| *reason)
+}
+
+// `EDITOR=vim git commit`
+fn is_env_assignment(word: &str) -> bool {
+  word.split_once('=').is_some_and(|(name, _)| {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+  })
+}
+
+#[cfg(not(windows))]
+fn shell_command(cmd: &str, _interactive: bool) -> Command {
+  let mut command = Command::new("sh");
+  command.arg("-c").arg(cmd);
+  command
+}
+
+// `powershell -Command` starts console programs without a usable console input
+// mode, so interactive ones go through `cmd /C`, given the command line untouched.
+// Under cmd.exe everything does, since the fix is written in cmd syntax.
+#[cfg(windows)]
+fn shell_command(cmd: &str, interactive: bool) -> Command {
+  use std::os::windows::process::CommandExt;
+
+  if interactive || crate::hook::running_in_cmd() {
+    let mut command = Command::new("cmd");
+    command.arg("/C").raw_arg(cmd);
+    command
+  } else {
+    let mut command = Command::new("powershell");
+    command.arg("-Command").arg(cmd);
+    command
+  }
+}
+
+// When wtf's own stdin or stdout is a pipe (`--stdin`, `wtf | tee`), hand the
+// child the controlling terminal instead, so an editor doesn't read a closed pipe.
+#[cfg(unix)]
+fn terminal_stdio() -> (Stdio, Stdio) {
+  use std::fs::{File, OpenOptions};
+  use std::io::{self, IsTerminal};
+
+  let open_tty = |write: bool| -> Option<File> {
+    OpenOptions::new()
+      .read(!write)
+      .write(write)
+      .open("/dev/tty")
+      .ok()
+  };
+
+  let stdin = match open_tty(false) {
+    Some(tty) if !io::stdin().is_terminal() => Stdio::from(tty),
+    _ => Stdio::inherit(),
+  };
+  let stdout = match open_tty(true) {
+    Some(tty) if !io::stdout().is_terminal() => Stdio::from(tty),
+    _ => Stdio::inherit(),
+  };
+
+  (stdin, stdout)
+}
+
+#[cfg(not(unix))]
+fn terminal_stdio() -> (Stdio, Stdio) {
+  (Stdio::inherit(), Stdio::inherit())
+}
+
+#[cfg(unix)]
+fn exit_code(status: std::process::ExitStatus) -> i32 {
+  use std::os::unix::process::ExitStatusExt;
+
+  // Killed by a signal: report it the way shells do.
+  status
+    .code()
+    .or_else(|| status.signal().map(|signal| 128 + signal))
+    .unwrap_or(1)
+}
+
+#[cfg(not(unix))]
+fn exit_code(status: std::process::ExitStatus) -> i32 {
+  status.code().unwrap_or(1)
 }
@@ -1,51 +1,382 @@
+use crate::config::{home_dir, wtf_dir, UserConfig};
+use crate::error::WtfError;
+use crate::hook;
+use crate::logging;
 use regex::Regex;
+use serde::Deserialize;
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ShellType {
   PowerShell,
   Bash,
   Zsh,
   Fish,
+  Xonsh,
 }
 
-pub fn get_last_command() -> Result<String, String> {
-  let history_path = get_history_path()?;
+impl ShellType {
+  pub fn name(&self) -> &'static str {
+    match self {
+      ShellType::PowerShell => "powershell",
+      ShellType::Bash => "bash",
+      ShellType::Zsh => "zsh",
+      ShellType::Fish => "fish",
+      ShellType::Xonsh => "xonsh",
+    }
+  }
 
-  if !history_path.exists() {
-    return Err(format!("History file not found: {:?}", history_path));
+  /// Every name `from_name` accepts, e.g. in `history_sources`
+  pub const NAMES: &'static [(&'static str, ShellType)] = &[
+    ("powershell", ShellType::PowerShell),
+    ("pwsh", ShellType::PowerShell),
+    ("bash", ShellType::Bash),
+    ("zsh", ShellType::Zsh),
+    ("fish", ShellType::Fish),
+    ("xonsh", ShellType::Xonsh),
+  ];
+
+  pub fn from_name(name: &str) -> Option<Self> {
+    let name = name.to_lowercase();
+    Self::NAMES
+      .iter()
+      .find(|(known, _)| *known == name)
+      .map(|(_, shell)| *shell)
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+  pub command: String,
+  pub timestamp: Option<u64>,
+  pub source: ShellType,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HistoryOptions {
+  /// Shell histories to merge (e.g. "powershell", "bash"). Empty means auto-detect one.
+  pub sources: Vec<String>,
+  /// Ignore entries older than this. None means no age limit.
+  pub since: Option<Duration>,
+  /// Keep `wtf` invocations, to debug what history actually recorded
+  pub include_wtf: bool,
+  /// Pick the latest command the shell hook recorded as failing, when there is one
+  pub prefer_failed: bool,
+}
+
+impl HistoryOptions {
+  pub fn from_config(config: &UserConfig) -> Self {
+    Self {
+      sources: config.history_sources.clone(),
+      since: None,
+      include_wtf: false,
+      prefer_failed: config.prefer_failed,
+    }
+  }
+}
+
+/// Parses a duration like `30s`, `5m`, `1h` or `2d`.
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+  let input = input.trim();
+  let split = input
+    .find(|c: char| !c.is_ascii_digit())
+    .unwrap_or(input.len());
+  let (number, unit) = input.split_at(split);
+
+  let invalid = || format!("Invalid duration: '{}' (expected e.g. 30s, 5m, 1h)", input);
+  let value: u64 = number.parse().map_err(|_| invalid())?;
+
+  let unit_seconds = match unit {
+    "s" => 1,
+    "m" => 60,
+    "h" => 60 * 60,
+    "d" => 60 * 60 * 24,
+    _ => {
+      return Err(format!(
+        "Invalid duration unit in '{}' (use s, m, h or d)",
+        input
+      ))
+    }
+  };
+  let seconds = value.checked_mul(unit_seconds).ok_or_else(invalid)?;
+
+  Ok(Duration::from_secs(seconds))
+}
+
+fn format_duration(duration: Duration) -> String {
+  let secs = duration.as_secs();
+  match secs {
+    s if s % 86400 == 0 && s > 0 => format!("{}d", s / 86400),
+    s if s % 3600 == 0 && s > 0 => format!("{}h", s / 3600),
+    s if s % 60 == 0 && s > 0 => format!("{}m", s / 60),
+    s => format!("{}s", s),
   }
+}
+
+// Set by the zsh hook from `fc -ln`: with SHARE_HISTORY the last command may not be
+// in $HISTFILE yet, while the shell itself always knows it.
+const HOOK_LAST_COMMAND_VAR: &str = "WTF_LAST_COMMAND";
 
-  let content =
-    fs::read_to_string(&history_path).map_err(|e| format!("Failed to read history: {}", e))?;
+pub fn get_last_command(options: &HistoryOptions) -> Result<String, WtfError> {
+  if options.prefer_failed {
+    if let Some(cmd) = last_failed_command(!options.include_wtf) {
+      logging::log(&format!("last failed command from {}", EXIT_LOG));
+      return Ok(cmd);
+    }
+  }
 
-  let shell_type = detect_shell_type(&history_path);
+  if let Some(cmd) = last_command_from_hook(!options.include_wtf) {
+    logging::log(&format!("last command from {}", HOOK_LAST_COMMAND_VAR));
+    return Ok(cmd);
+  }
 
-  let result = match shell_type {
-    ShellType::PowerShell => parse_powershell_history(&content),
-    ShellType::Bash | ShellType::Zsh => parse_bash_zsh_history(&content),
-    ShellType::Fish => parse_fish_history(&content),
+  get_recent_commands(1, options)?
+    .into_iter()
+    .next()
+    .map(|entry| entry.command)
+    .ok_or_else(|| WtfError::HistoryNotFound("No valid command found in history".to_string()))
+}
+
+pub fn get_recent_commands(
+  limit: usize,
+  options: &HistoryOptions,
+) -> Result<Vec<HistoryEntry>, WtfError> {
+  let entries = read_all_sources(options)?;
+
+  let Some(since) = options.since else {
+    return Ok(entries.into_iter().take(limit).collect());
   };
 
-  if result.is_err() && matches!(shell_type, ShellType::Bash) {
-    return Err(
-      "History file is not up to date. Add this to your ~/.bashrc:\n\
+  let cutoff = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|now| now.as_secs().saturating_sub(since.as_secs()))
+    .unwrap_or(0);
+
+  let recent: Vec<HistoryEntry> = entries
+    .into_iter()
+    .filter(|entry| entry.timestamp.is_none_or(|ts| ts >= cutoff))
+    .take(limit)
+    .collect();
+
+  if recent.is_empty() {
+    return Err(WtfError::HistoryStale(format!(
+      "The most recent command is older than {}",
+      format_duration(since)
+    )));
+  }
+
+  Ok(recent)
+}
+
+// Reads every configured source, newest first.
+fn read_all_sources(options: &HistoryOptions) -> Result<Vec<HistoryEntry>, WtfError> {
+  let skip_self = !options.include_wtf;
+
+  if options.sources.is_empty() {
+    if hook::running_in_cmd() {
+      return Err(WtfError::HistoryNotFound(
+        "cmd.exe doesn't keep a history file, so there is no last command to read.\n\
+         Pass the command directly: wtf fix \"<command>\""
+          .to_string(),
+      ));
+    }
+
+    if let Some((history_path, shell_type)) = parent_shell_history() {
+      return read_history(&history_path, shell_type, skip_self);
+    }
+
+    let history_path = get_history_path()?;
+    let shell_type = detect_shell_type(&history_path);
+    return read_history(&history_path, shell_type, skip_self);
+  }
+
+  let mut entries = Vec::new();
+  let mut errors = Vec::new();
+
+  for source in &options.sources {
+    let shell_type = ShellType::from_name(source)
+      .ok_or_else(|| WtfError::ConfigError(format!("Unknown history source: {}", source)))?;
+
+    let result = get_source_history_path(shell_type)
+      .and_then(|history_path| read_history(&history_path, shell_type, skip_self));
+
+    match result {
+      Ok(source_entries) => entries.extend(source_entries),
+      Err(e) => errors.push(format!("{}: {}", shell_type.name(), e)),
+    }
+  }
+
+  if entries.is_empty() {
+    return Err(WtfError::HistoryNotFound(format!(
+      "No valid command found in history sources ({})",
+      errors.join("; ")
+    )));
+  }
+
+  // Stable sort keeps each source's own newest-first order for equal timestamps.
+  entries.sort_by_key(|entry| std::cmp::Reverse(entry.timestamp));
+
+  Ok(entries)
+}
+
+// Checked instead of the `.gz` extension, so a renamed or rotated file works too.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>, WtfError> {
+  use flate2::read::MultiGzDecoder;
+  use std::io::Read;
+
+  // Multi-member, since rotated logs are sometimes concatenated
+  let mut decompressed = Vec::new();
+  MultiGzDecoder::new(bytes)
+    .read_to_end(&mut decompressed)
+    .map_err(|e| WtfError::HistoryUnreadable(format!("Failed to decompress history: {}", e)))?;
+  Ok(decompressed)
+}
+
+// `skip_self` drops `wtf` invocations, which is what every caller wants outside of debugging.
+fn read_history(
+  history_path: &Path,
+  shell_type: ShellType,
+  skip_self: bool,
+) -> Result<Vec<HistoryEntry>, WtfError> {
+  if !history_path.exists() {
+    return Err(WtfError::HistoryNotFound(format!(
+      "History file not found: {:?}",
+      history_path
+    )));
+  }
+
+  logging::log(&format!(
+    "reading {} history from {}",
+    shell_type.name(),
+    history_path.display()
+  ));
+  let bytes = fs::read(history_path)
+    .map_err(|e| WtfError::HistoryUnreadable(format!("Failed to read history: {}", e)))?;
+  let bytes = if bytes.starts_with(&GZIP_MAGIC) {
+    gunzip(&bytes)?
+  } else {
+    bytes
+  };
+  let bytes = if shell_type == ShellType::Zsh {
+    unmetafy(&bytes)
+  } else {
+    bytes
+  };
+  // One stray invalid byte (e.g. from a crashed shell) shouldn't hide every other entry.
+  let content = String::from_utf8_lossy(&bytes);
+
+  let mut entries = match shell_type {
+    ShellType::PowerShell => parse_powershell_history(&content, skip_self),
+    ShellType::Bash | ShellType::Zsh => parse_bash_zsh_history(&content, shell_type, skip_self),
+    ShellType::Fish => parse_fish_history(&content, skip_self),
+    ShellType::Xonsh => parse_xonsh_history(&content, skip_self),
+  };
+
+  if entries.is_empty() {
+    if shell_type == ShellType::Bash {
+      return Err(WtfError::HistoryNotFound(
+        "History file is not up to date. Add this to your ~/.bashrc:\n\
              shopt -s histappend\n\
              PROMPT_COMMAND='history -a'"
-        .to_string(),
-    );
+          .to_string(),
+      ));
+    }
+    return Err(WtfError::HistoryNotFound(
+      "No valid command found in history".to_string(),
+    ));
   }
 
-  result
+  // Entries without their own timestamp fall back to the file's modification time,
+  // so sources can still be merged in a sensible order.
+  let modified = fs::metadata(history_path)
+    .and_then(|m| m.modified())
+    .ok()
+    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+    .map(|d| d.as_secs());
+
+  for entry in entries.iter_mut() {
+    if entry.timestamp.is_none() {
+      entry.timestamp = modified;
+    }
+  }
+
+  Ok(entries)
 }
 
-fn detect_shell_type(path: &PathBuf) -> ShellType {
+// zsh writes bytes 0x83-0xa2 (and NUL) to $HISTFILE as 0x83 followed by the byte
+// xor 0x20, so `echo café` needs decoding before it's valid UTF-8 again.
+const ZSH_META: u8 = 0x83;
+
+fn unmetafy(bytes: &[u8]) -> Vec<u8> {
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut iter = bytes.iter();
+
+  while let Some(&byte) = iter.next() {
+    match byte {
+      ZSH_META => match iter.next() {
+        Some(&next) => decoded.push(next ^ 0x20),
+        None => decoded.push(byte),
+      },
+      _ => decoded.push(byte),
+    }
+  }
+
+  decoded
+}
+
+// The shell that launched us is a better guide than $SHELL or file names: it is the
+// history the user just typed into. None when it can't be told or has no history file.
+fn parent_shell_history() -> Option<(PathBuf, ShellType)> {
+  let shell_type = parent_shell()?;
+
+  // HISTFILE is only visible when exported, but then it's the one this shell writes
+  let path = match env::var("HISTFILE").map(PathBuf::from) {
+    Ok(path) if path.exists() => path,
+    _ => get_source_history_path(shell_type).ok()?,
+  };
+
+  path.exists().then_some((path, shell_type))
+}
+
+#[cfg(unix)]
+fn parent_shell() -> Option<ShellType> {
+  let ppid = std::os::unix::process::parent_id();
+
+  let name = match fs::read_to_string(format!("/proc/{}/comm", ppid)) {
+    Ok(comm) => comm,
+    // No procfs (macOS, BSD)
+    Err(_) => {
+      let output = std::process::Command::new("ps")
+        .args(["-o", "comm=", "-p", &ppid.to_string()])
+        .output()
+        .ok()?;
+      String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+  };
+
+  // Login shells show up as `-zsh`, and `ps` may print the full path
+  let name = name.trim().trim_start_matches('-');
+  let name = name.rsplit('/').next().unwrap_or(name);
+  ShellType::from_name(name)
+}
+
+#[cfg(not(unix))]
+fn parent_shell() -> Option<ShellType> {
+  None
+}
+
+fn detect_shell_type(path: &Path) -> ShellType {
   let path_str = path.to_string_lossy().to_lowercase();
 
   if path_str.contains("powershell") || path_str.contains("consolehost_history") {
     ShellType::PowerShell
+  } else if path_str.contains("xonsh") {
+    ShellType::Xonsh
   } else if path_str.contains("fish") {
     ShellType::Fish
   } else if path_str.contains("zsh") {
@@ -55,7 +386,107 @@ fn detect_shell_type(path: &PathBuf) -> ShellType {
   }
 }
 
-fn get_history_path() -> Result<PathBuf, String> {
+fn get_source_history_path(shell_type: ShellType) -> Result<PathBuf, WtfError> {
+  if let Ok(histfile) = env::var("HISTFILE") {
+    let path = PathBuf::from(&histfile);
+    if path.exists() && detect_shell_type(&path) == shell_type {
+      return Ok(path);
+    }
+  }
+
+  let home = home_dir()?;
+
+  let path = match shell_type {
+    ShellType::Bash => home.join(".bash_history"),
+    ShellType::Zsh => home.join(".zsh_history"),
+    ShellType::Fish => home.join(".local/share/fish/fish_history"),
+    ShellType::PowerShell => return get_powershell_history_path(&home),
+    ShellType::Xonsh => return get_xonsh_history_path(&home),
+  };
+
+  Ok(compressed_fallback(path))
+}
+
+// `~/.bash_history.gz` when only the compressed copy is left, e.g. after logrotate.
+fn compressed_fallback(path: PathBuf) -> PathBuf {
+  if path.exists() {
+    return path;
+  }
+
+  let mut compressed = path.clone().into_os_string();
+  compressed.push(".gz");
+  let compressed = PathBuf::from(compressed);
+  if compressed.exists() {
+    compressed
+  } else {
+    path
+  }
+}
+
+fn get_powershell_history_path(home: &Path) -> Result<PathBuf, WtfError> {
+  let psreadline = |base: PathBuf| {
+    base
+      .join("Microsoft")
+      .join("Windows")
+      .join("PowerShell")
+      .join("PSReadLine")
+      .join("ConsoleHost_history.txt")
+  };
+
+  if let Ok(appdata) = env::var("APPDATA") {
+    let path = psreadline(PathBuf::from(appdata));
+    if path.exists() {
+      return Ok(path);
+    }
+  }
+
+  // PowerShell Core on Linux/macOS
+  let pwsh = home.join(".local/share/powershell/PSReadLine/ConsoleHost_history.txt");
+  if pwsh.exists() {
+    return Ok(pwsh);
+  }
+
+  // Inside WSL, the Windows history lives under the mounted C: drive.
+  // Pick the most recently updated one if several users exist.
+  let newest = fs::read_dir("/mnt/c/Users")
+    .into_iter()
+    .flatten()
+    .flatten()
+    .map(|user| psreadline(user.path().join("AppData").join("Roaming")))
+    .filter(|path| path.exists())
+    .max_by_key(|path| fs::metadata(path).and_then(|m| m.modified()).ok());
+
+  newest.ok_or_else(|| WtfError::HistoryNotFound("PowerShell history not found".to_string()))
+}
+
+// Xonsh's JSON backend writes one file per session, so the newest one holds the last
+// command. The running session names its own file in $XONSH_HISTORY_FILE.
+fn get_xonsh_history_path(home: &Path) -> Result<PathBuf, WtfError> {
+  if let Ok(histfile) = env::var("XONSH_HISTORY_FILE") {
+    let path = PathBuf::from(histfile);
+    if path.exists() && path.extension().is_some_and(|ext| ext == "json") {
+      return Ok(path);
+    }
+  }
+
+  let data_dir = env::var("XONSH_DATA_DIR")
+    .map(PathBuf::from)
+    .unwrap_or_else(|_| home.join(".local/share/xonsh"));
+
+  // Older versions kept the session files directly in the data dir
+  let newest = [data_dir.join("history_json"), data_dir]
+    .iter()
+    .flat_map(fs::read_dir)
+    .flatten()
+    .flatten()
+    .map(|entry| entry.path())
+    .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+    .max_by_key(|path| fs::metadata(path).and_then(|m| m.modified()).ok());
+
+  newest.ok_or_else(|| WtfError::HistoryNotFound("Xonsh history not found".to_string()))
+}
+
+fn get_history_path() -> Result<PathBuf, WtfError> {
   if cfg!(target_os = "windows") {
     if let Ok(appdata) = env::var("APPDATA") {
       let ps_history = PathBuf::from(appdata)
@@ -69,79 +500,274 @@ fn get_history_path() -> Result<PathBuf, String> {
         return Ok(ps_history);
       }
     }
-    Err("PowerShell history not found".to_string())
+    Err(WtfError::HistoryNotFound(
+      "PowerShell history not found".to_string(),
+    ))
   } else {
-    let home = dirs::home_dir().ok_or("Home directory not found")?;
-
     if let Ok(histfile) = env::var("HISTFILE") {
-      let path = PathBuf::from(histfile);
+      let path = compressed_fallback(PathBuf::from(histfile));
       if path.exists() {
         return Ok(path);
       }
     }
 
+    let home = home_dir()?;
+
     let possible_paths = vec![
       home.join(".zsh_history"),
       home.join(".bash_history"),
       home.join(".local/share/fish/fish_history"),
     ];
 
-    for path in possible_paths {
+    for path in possible_paths.into_iter().map(compressed_fallback) {
       if path.exists() {
         return Ok(path);
       }
     }
 
-    Err("No shell history file found".to_string())
+    if let Ok(path) = get_xonsh_history_path(&home) {
+      return Ok(path);
+    }
+
+    Err(WtfError::HistoryNotFound(
+      "No shell history file found".to_string(),
+    ))
   }
 }
 
-fn parse_powershell_history(content: &str) -> Result<String, String> {
+// Written by the hook's exit code recorder, one "<shell pid>\t<exit code>\t<command>" per line.
+const EXIT_LOG: &str = "exit_codes";
+
+// A failure further back than this many commands is probably already dealt with.
+const FAILED_LOOKBACK: usize = 10;
+
+// The recorder only ever appends, so the log is cut back to half once it reaches this.
+const EXIT_LOG_MAX_LINES: usize = 1000;
+
+// The newest failing command among this shell's last few; None without the
+// recorder, or when they all succeeded, so the caller falls back to the latest one.
+fn last_failed_command(skip_self: bool) -> Option<String> {
+  let path = wtf_dir().ok()?.join(EXIT_LOG);
+  let content = fs::read_to_string(&path).ok()?;
   let lines: Vec<&str> = content.lines().collect();
 
-  if lines.len() < 2 {
-    return Err("Not enough history".to_string());
+  if lines.len() >= EXIT_LOG_MAX_LINES {
+    // Swapped in whole, so a prompt appending meanwhile never meets a half-written file
+    let kept = lines[lines.len() - EXIT_LOG_MAX_LINES / 2..].join("\n");
+    let temp = path.with_file_name(format!("{}.{}", EXIT_LOG, std::process::id()));
+    let swapped = fs::write(&temp, kept + "\n").and_then(|_| fs::rename(&temp, &path));
+    if swapped.is_err() {
+      let _ = fs::remove_file(&temp);
+    }
   }
 
-  Ok(lines[lines.len() - 2].trim().to_string())
+  // Set by the hook, so other terminals' commands don't count
+  let shell_pid = env::var("WTF_SHELL_PID").ok();
+
+  lines
+    .iter()
+    .rev()
+    .filter_map(|line| {
+      let mut fields = line.splitn(3, '\t');
+      let pid = fields.next()?;
+      let status: i32 = fields.next()?.parse().ok()?;
+      let cmd = fields.next()?.trim();
+      Some((pid, status, cmd))
+    })
+    .filter(|(pid, _, _)| shell_pid.as_deref().is_none_or(|shell| shell == *pid))
+    .filter(|(_, _, cmd)| is_valid_command(cmd, skip_self))
+    .take(FAILED_LOOKBACK)
+    .find(|(_, status, _)| *status != 0)
+    .map(|(_, _, cmd)| cmd.to_string())
 }
 
-fn parse_bash_zsh_history(content: &str) -> Result<String, String> {
-  let lines: Vec<&str> = content.lines().collect();
+// The hook lists the last couple of entries, oldest first; the newest is usually `wtf` itself.
+fn last_command_from_hook(skip_self: bool) -> Option<String> {
+  let listed = env::var(HOOK_LAST_COMMAND_VAR).ok()?;
+
+  listed
+    .lines()
+    .rev()
+    .map(|line| line.trim())
+    .find(|cmd| is_valid_command(cmd, skip_self))
+    .map(|cmd| cmd.to_string())
+}
 
-  if lines.is_empty() {
-    return Err("Empty history".to_string());
+fn is_valid_command(cmd: &str, skip_self: bool) -> bool {
+  if cmd.is_empty() {
+    return false;
   }
+  !skip_self || !cmd.starts_with("wtf")
+}
+
+// Returned entries are ordered newest first.
+fn parse_powershell_history(content: &str, skip_self: bool) -> Vec<HistoryEntry> {
+  let lines: Vec<&str> = content.lines().collect();
+
+  // On Windows the last line is the currently running `wtf` invocation.
+  let lines = if cfg!(target_os = "windows") && skip_self {
+    &lines[..lines.len().saturating_sub(1)]
+  } else {
+    &lines[..]
+  };
+
+  lines
+    .iter()
+    .rev()
+    .map(|line| line.trim())
+    .filter(|cmd| is_valid_command(cmd, skip_self))
+    .map(|cmd| HistoryEntry {
+      command: cmd.to_string(),
+      timestamp: None,
+      source: ShellType::PowerShell,
+    })
+    .collect()
+}
 
-  let re = Regex::new(r"^: \d+:\d+;(.+)$").unwrap();
+fn parse_bash_zsh_history(
+  content: &str,
+  shell_type: ShellType,
+  skip_self: bool,
+) -> Vec<HistoryEntry> {
+  let zsh_re = Regex::new(r"^: (\d+):\d+;(.+)$").unwrap();
+  let bash_time_re = Regex::new(r"^#(\d+)$").unwrap();
+
+  let mut entries = Vec::new();
+  let mut pending_timestamp = None;
+
+  for line in content.lines() {
+    // With HISTTIMEFORMAT set, bash writes `#<epoch>` before each command
+    if let Some(caps) = bash_time_re.captures(line.trim()) {
+      pending_timestamp = caps[1].parse().ok();
+      continue;
+    }
 
-  for line in lines.iter().rev() {
-    let cmd = if let Some(caps) = re.captures(line) {
-      caps.get(1).unwrap().as_str()
+    let (cmd, timestamp) = if let Some(caps) = zsh_re.captures(line) {
+      (caps.get(2).unwrap().as_str(), caps[1].parse().ok())
     } else {
-      line.trim()
+      (line.trim(), pending_timestamp)
     };
+    pending_timestamp = None;
 
-    if !cmd.starts_with("wtf") && !cmd.is_empty() {
-      return Ok(cmd.to_string());
+    if is_valid_command(cmd, skip_self) {
+      entries.push(HistoryEntry {
+        command: cmd.to_string(),
+        timestamp,
+        source: shell_type,
+      });
     }
   }
 
-  Err("No valid command found in history".to_string())
+  entries.reverse();
+  entries
 }
 
-fn parse_fish_history(content: &str) -> Result<String, String> {
-  let re = Regex::new(r"- cmd: (.+)").unwrap();
-  let lines: Vec<&str> = content.lines().collect();
+fn parse_fish_history(content: &str, skip_self: bool) -> Vec<HistoryEntry> {
+  let cmd_re = Regex::new(r"- cmd: (.+)").unwrap();
+  let when_re = Regex::new(r"^\s*when: (\d+)").unwrap();
+
+  let mut entries: Vec<HistoryEntry> = Vec::new();
 
-  for line in lines.iter().rev() {
-    if let Some(caps) = re.captures(line) {
-      let cmd = caps.get(1).unwrap().as_str().trim();
-      if !cmd.starts_with("wtf") && !cmd.is_empty() {
-        return Ok(cmd.to_string());
+  for line in content.lines() {
+    if let Some(caps) = cmd_re.captures(line) {
+      entries.push(HistoryEntry {
+        command: caps.get(1).unwrap().as_str().trim().to_string(),
+        timestamp: None,
+        source: ShellType::Fish,
+      });
+    } else if let Some(caps) = when_re.captures(line) {
+      if let Some(last) = entries.last_mut() {
+        last.timestamp = caps[1].parse().ok();
       }
     }
   }
 
-  Err("No valid command found in history".to_string())
+  entries.retain(|entry| is_valid_command(&entry.command, skip_self));
+  entries.reverse();
+  entries
+}
+
+#[derive(Deserialize)]
+struct XonshSession {
+  data: XonshData,
+}
+
+#[derive(Deserialize)]
+struct XonshData {
+  #[serde(default)]
+  cmds: Vec<XonshCommand>,
+}
+
+#[derive(Deserialize)]
+struct XonshCommand {
+  inp: String,
+  /// Start and end time, as fractional epoch seconds
+  #[serde(default)]
+  ts: Vec<f64>,
+}
+
+fn parse_xonsh_history(content: &str, skip_self: bool) -> Vec<HistoryEntry> {
+  // A session file that is still being flushed can't be parsed; it has no usable entries
+  let Ok(session) = serde_json::from_str::<XonshSession>(content) else {
+    return Vec::new();
+  };
+
+  let mut entries: Vec<HistoryEntry> = session
+    .data
+    .cmds
+    .into_iter()
+    .map(|cmd| HistoryEntry {
+      command: cmd.inp.trim().to_string(),
+      timestamp: cmd.ts.first().map(|ts| *ts as u64),
+      source: ShellType::Xonsh,
+    })
+    .collect();
+
+  entries.retain(|entry| is_valid_command(&entry.command, skip_self));
+  entries.reverse();
+  entries
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn read_history_skips_over_invalid_utf8() {
+    let path = env::temp_dir().join(format!("wtf-history-test-{}", std::process::id()));
+    fs::write(&path, b"ls -la\necho \xff\xfe\ngti status\n").unwrap();
+    let entries = read_history(&path, ShellType::Bash, false);
+    fs::remove_file(&path).ok();
+
+    let commands: Vec<String> = entries.unwrap().into_iter().map(|e| e.command).collect();
+    assert!(commands.contains(&"ls -la".to_string()));
+    assert!(commands.contains(&"gti status".to_string()));
+  }
+
+  // What zsh writes to $HISTFILE
+  fn metafy(text: &str) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    for &byte in text.as_bytes() {
+      if byte == 0 || (ZSH_META..=0xa2).contains(&byte) {
+        encoded.push(ZSH_META);
+        encoded.push(byte ^ 0x20);
+      } else {
+        encoded.push(byte);
+      }
+    }
+    encoded
+  }
+
+  #[test]
+  fn unmetafy_round_trips_escaped_bytes() {
+    let text = "echo \u{2014} caf\u{e9} \u{2713}";
+    let encoded = metafy(text);
+    assert!(encoded.contains(&ZSH_META));
+    assert_eq!(unmetafy(&encoded), text.as_bytes());
+  }
+
+  #[test]
+  fn unmetafy_keeps_a_trailing_meta_byte() {
+    assert_eq!(unmetafy(b"echo \x83"), b"echo \x83");
+  }
 }
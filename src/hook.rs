@@ -0,0 +1,228 @@
+use std::env;
+
+// `wtf` asks before running a fix, `wtff` runs the first one straight away (`--yes`).
+// Both eval the fix in the current shell, so `cd` fixes stick.
+const BASH_ZSH_HOOK: &str = r#"__wtf_eval() {
+  local __wtf_cmd __wtf_last=""
+  # zsh may not have written the last command to $HISTFILE yet
+  if [ -n "$ZSH_VERSION" ]; then
+    __wtf_last="$(fc -ln -2 2>/dev/null)"
+  fi
+  __wtf_cmd="$(WTF_LAST_COMMAND="$__wtf_last" WTF_SHELL_PID=$$ command wtf "$@" eval)" || return $?
+  [ -n "$__wtf_cmd" ] || return 0
+  if [ -n "$ZSH_VERSION" ]; then
+    print -s -- "$__wtf_cmd"
+  else
+    history -s -- "$__wtf_cmd"
+  fi
+  eval "$__wtf_cmd"
+}
+wtf() {
+  if [ "$#" -eq 0 ]; then
+    __wtf_eval
+  else
+    WTF_SHELL_PID=$$ command wtf "$@"
+  fi
+}
+wtff() {
+  __wtf_eval --yes "$@"
+}
+"#;
+
+// `prefer_failed`: each prompt appends "<shell pid>\t<exit code>\t<command>" to ~/.wtf/exit_codes.
+// It runs first in PROMPT_COMMAND / precmd and passes the exit code on, so prompts still see it.
+const BASH_ZSH_EXIT_RECORDER: &str = r#"__wtf_record_exit() {
+  local __wtf_status=$? __wtf_line __wtf_dir="${WTF_HOME:-$HOME}/.wtf"
+  if [ -n "$ZSH_VERSION" ]; then
+    __wtf_line="$(fc -ln -1 2>/dev/null)"
+  else
+    # bash's `fc -ln -1` lags a command behind inside PROMPT_COMMAND
+    __wtf_line="$(HISTTIMEFORMAT= builtin history 1)"
+    __wtf_line="${__wtf_line#*[0-9]  }"
+  fi
+  __wtf_line="${__wtf_line//$'\n'/ }"
+  __wtf_line="${__wtf_line#"${__wtf_line%%[![:space:]]*}"}"
+  if [ -n "$__wtf_line" ] && [ -d "$__wtf_dir" ]; then
+    printf '%s\t%s\t%s\n' "$$" "$__wtf_status" "$__wtf_line" >> "$__wtf_dir/exit_codes"
+  fi
+  return $__wtf_status
+}
+if [ -n "$ZSH_VERSION" ]; then
+  precmd_functions=(__wtf_record_exit $precmd_functions)
+else
+  PROMPT_COMMAND="__wtf_record_exit${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
+fi
+"#;
+
+const FISH_HOOK: &str = r#"function __wtf_eval
+    set -lx WTF_SHELL_PID $fish_pid
+    set -l __wtf_cmd (command wtf $argv eval)
+    or return $status
+    test -n "$__wtf_cmd"
+    or return 0
+    builtin history append -- "$__wtf_cmd"
+    eval $__wtf_cmd
+end
+function wtf
+    if test (count $argv) -eq 0
+        __wtf_eval
+    else
+        set -lx WTF_SHELL_PID $fish_pid
+        command wtf $argv
+    end
+end
+function wtff
+    __wtf_eval --yes $argv
+end
+"#;
+
+const FISH_EXIT_RECORDER: &str = r#"function __wtf_record_exit --on-event fish_postexec
+    set -l __wtf_status $status
+    set -l __wtf_dir $HOME/.wtf
+    set -q WTF_HOME; and test -n "$WTF_HOME"; and set __wtf_dir $WTF_HOME/.wtf
+    test -n "$argv[1]"; and test -d $__wtf_dir; or return
+    printf '%s\t%s\t%s\n' $fish_pid $__wtf_status (string join ' ' -- (string split \n -- $argv[1])) >>$__wtf_dir/exit_codes
+end
+"#;
+
+const POWERSHELL_HOOK: &str = r#"function wtf {
+  $wtfExe = Get-Command wtf -CommandType Application | Select-Object -First 1
+  if ($args.Count -eq 0) {
+    $wtfCmd = & $wtfExe eval
+    if ($LASTEXITCODE -eq 0 -and $wtfCmd) {
+      [Microsoft.PowerShell.PSConsoleReadLine]::AddToHistory($wtfCmd)
+      Invoke-Expression $wtfCmd
+    }
+  } else {
+    & $wtfExe @args
+  }
+}
+function wtff {
+  $wtfExe = Get-Command wtf -CommandType Application | Select-Object -First 1
+  $wtfCmd = & $wtfExe --yes @args eval
+  if ($LASTEXITCODE -eq 0 -and $wtfCmd) {
+    [Microsoft.PowerShell.PSConsoleReadLine]::AddToHistory($wtfCmd)
+    Invoke-Expression $wtfCmd
+  }
+}
+"#;
+
+// Decided by the nearest shell among wtf's parent processes. An environment variable
+// can't tell: PowerShell started from cmd.exe inherits cmd's PROMPT.
+pub fn running_in_cmd() -> bool {
+  #[cfg(windows)]
+  {
+    static IN_CMD: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *IN_CMD.get_or_init(|| windows_parent::nearest_shell().as_deref() == Some("cmd.exe"))
+  }
+  #[cfg(not(windows))]
+  false
+}
+
+#[cfg(windows)]
+mod windows_parent {
+  use std::collections::HashMap;
+  use windows_sys::Win32::Foundation::{CloseHandle, INVALID_HANDLE_VALUE};
+  use windows_sys::Win32::System::Diagnostics::ToolHelp::{
+    CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W, TH32CS_SNAPPROCESS,
+  };
+
+  const SHELLS: &[&str] = &[
+    "cmd.exe",
+    "powershell.exe",
+    "pwsh.exe",
+    "bash.exe",
+    "nu.exe",
+  ];
+
+  /// Lowercased image name of the closest ancestor that is a known shell, skipping
+  /// launchers like shims in between.
+  pub fn nearest_shell() -> Option<String> {
+    let processes = process_table();
+    let mut pid = std::process::id();
+
+    // Bounded, since a recycled PID can make the parent chain loop
+    for _ in 0..16 {
+      let (parent, _) = processes.get(&pid)?;
+      let (_, name) = processes.get(parent)?;
+      if SHELLS.contains(&name.as_str()) {
+        return Some(name.clone());
+      }
+      pid = *parent;
+    }
+    None
+  }
+
+  // PID -> (parent PID, lowercased image name)
+  fn process_table() -> HashMap<u32, (u32, String)> {
+    let mut processes = HashMap::new();
+
+    unsafe {
+      let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+      if snapshot == INVALID_HANDLE_VALUE {
+        return processes;
+      }
+
+      let mut entry: PROCESSENTRY32W = std::mem::zeroed();
+      entry.dwSize = std::mem::size_of::<PROCESSENTRY32W>() as u32;
+
+      let mut more = Process32FirstW(snapshot, &mut entry) != 0;
+      while more {
+        let len = entry
+          .szExeFile
+          .iter()
+          .position(|&c| c == 0)
+          .unwrap_or(entry.szExeFile.len());
+        let name = String::from_utf16_lossy(&entry.szExeFile[..len]).to_lowercase();
+        processes.insert(entry.th32ProcessID, (entry.th32ParentProcessID, name));
+        more = Process32NextW(snapshot, &mut entry) != 0;
+      }
+
+      CloseHandle(snapshot);
+    }
+
+    processes
+  }
+}
+
+pub fn detect_shell() -> String {
+  if running_in_cmd() {
+    return "cmd".to_string();
+  }
+  if cfg!(target_os = "windows") {
+    return "powershell".to_string();
+  }
+
+  env::var("SHELL")
+    .ok()
+    .and_then(|shell| shell.split('/').next_back().map(|name| name.to_string()))
+    .unwrap_or_else(|| "bash".to_string())
+}
+
+/// With `record_exit_codes` (the `prefer_failed` setting), bash, zsh and fish also
+/// log each command's exit code for `wtf` to find the last failure.
+pub fn hook_script(shell: &str, record_exit_codes: bool) -> Result<String, String> {
+  let (hook, recorder) = match shell.to_lowercase().as_str() {
+    "bash" | "zsh" => (BASH_ZSH_HOOK, BASH_ZSH_EXIT_RECORDER),
+    "fish" => (FISH_HOOK, FISH_EXIT_RECORDER),
+    "powershell" | "pwsh" => (POWERSHELL_HOOK, ""),
+    "cmd" => {
+      return Err(
+        "cmd.exe has no shell functions to hook into; run `wtf fix \"<command>\"` instead"
+          .to_string(),
+      )
+    }
+    other => {
+      return Err(format!(
+        "Unsupported shell for hook: {} (supported: bash, zsh, fish, powershell)",
+        other
+      ))
+    }
+  };
+
+  if record_exit_codes {
+    Ok(format!("{}{}", hook, recorder))
+  } else {
+    Ok(hook.to_string())
+  }
+}
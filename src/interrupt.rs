@@ -0,0 +1,65 @@
+// Ctrl-C anywhere in wtf (a prompt, an AI request, the typo editor) puts the terminal
+// back and exits with 130, like shells report an interrupted command.
+
+use crate::logging;
+use crossterm::cursor::Show;
+use crossterm::execute;
+use crossterm::terminal;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const EXIT_INTERRUPTED: i32 = 130;
+
+// Commands wtf is waiting on. They get the Ctrl-C too and decide for themselves;
+// wtf then reports their exit code as usual.
+static CHILDREN: AtomicUsize = AtomicUsize::new(0);
+
+pub fn install() {
+  let result = ctrlc::set_handler(|| {
+    if CHILDREN.load(Ordering::SeqCst) > 0 {
+      return;
+    }
+
+    // Also drops any AI request still in flight
+    exit_interrupted();
+  });
+
+  if let Err(e) = result {
+    logging::log(&format!("failed to install Ctrl-C handler: {}", e));
+  }
+}
+
+/// Leaves the way a Ctrl-C does. Raw mode swallows the signal, so the typo editor
+/// reads Ctrl-C as a key and calls this itself.
+pub fn exit_interrupted() -> ! {
+  restore_terminal();
+  logging::log("interrupted");
+  std::process::exit(EXIT_INTERRUPTED);
+}
+
+fn restore_terminal() {
+  let _ = terminal::disable_raw_mode();
+
+  let mut stderr = io::stderr();
+  if stderr.is_terminal() {
+    // The ^C usually lands mid-prompt, so start the shell's prompt on a fresh line
+    let _ = execute!(stderr, Show);
+    let _ = writeln!(stderr);
+  }
+}
+
+/// Marks a child process as running until dropped.
+pub struct ChildGuard;
+
+impl ChildGuard {
+  pub fn new() -> Self {
+    CHILDREN.fetch_add(1, Ordering::SeqCst);
+    ChildGuard
+  }
+}
+
+impl Drop for ChildGuard {
+  fn drop(&mut self) {
+    CHILDREN.fetch_sub(1, Ordering::SeqCst);
+  }
+}
@@ -0,0 +1,96 @@
+// Fixes remembered per project (`remember_per_directory`): the same typo can mean
+// different things in different repos, so they're keyed by the git root, or the
+// current directory outside a repo.
+
+use crate::config::wtf_dir;
+use crate::error::WtfError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LearnedFixes {
+  /// Directory -> typed command -> accepted fix
+  #[serde(default)]
+  pub dirs: HashMap<String, HashMap<String, String>>,
+}
+
+impl LearnedFixes {
+  pub fn load() -> Self {
+    learned_path()
+      .ok()
+      .and_then(|path| fs::read_to_string(path).ok())
+      .and_then(|content| serde_json::from_str(&content).ok())
+      .unwrap_or_default()
+  }
+
+  /// Saves, dropping directories that have since been deleted.
+  pub fn save(&mut self) -> Result<(), WtfError> {
+    self.dirs.retain(|dir, _| Path::new(dir).is_dir());
+
+    let path = learned_path()?;
+
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent)
+        .map_err(|e| WtfError::ConfigError(format!("Failed to create config directory: {}", e)))?;
+    }
+
+    let content = serde_json::to_string_pretty(self)
+      .map_err(|e| WtfError::ConfigError(format!("Failed to serialize learned fixes: {}", e)))?;
+
+    fs::write(&path, content)
+      .map_err(|e| WtfError::ConfigError(format!("Failed to write learned fixes: {}", e)))
+  }
+
+  pub fn remember(&mut self, dir: &Path, cmd: &str, fixed_cmd: &str) {
+    self
+      .dirs
+      .entry(dir.to_string_lossy().into_owned())
+      .or_default()
+      .insert(cmd.to_string(), fixed_cmd.to_string());
+  }
+
+  fn get(&self, dir: &Path, cmd: &str) -> Option<&str> {
+    self
+      .dirs
+      .get(dir.to_string_lossy().as_ref())?
+      .get(cmd)
+      .map(String::as_str)
+  }
+}
+
+fn learned_path() -> Result<PathBuf, WtfError> {
+  Ok(wtf_dir()?.join("learned.json"))
+}
+
+/// The git root of the current directory, or the directory itself outside a repo.
+pub fn scope_dir() -> Option<PathBuf> {
+  let cwd = env::current_dir().ok()?;
+  let root = cwd
+    .ancestors()
+    .find(|dir| dir.join(".git").exists())
+    .unwrap_or(&cwd);
+  Some(root.to_path_buf())
+}
+
+/// The fix accepted for exactly this command in this project, read once per run.
+pub fn learned_fix(cmd: &str) -> Option<&'static str> {
+  static LEARNED: OnceLock<(Option<PathBuf>, LearnedFixes)> = OnceLock::new();
+
+  let (dir, learned) = LEARNED.get_or_init(|| (scope_dir(), LearnedFixes::load()));
+  learned.get(dir.as_deref()?, cmd)
+}
+
+/// Records an accepted fix for the current project.
+pub fn remember_fix(cmd: &str, fixed_cmd: &str) -> Result<(), WtfError> {
+  let Some(dir) = scope_dir() else {
+    return Ok(());
+  };
+
+  let mut learned = LearnedFixes::load();
+  learned.remember(&dir, cmd, fixed_cmd);
+  learned.save()
+}
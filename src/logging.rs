@@ -0,0 +1,60 @@
+// Opt-in troubleshooting log. `WTF_LOG=debug` appends what wtf saw and did to
+// ~/.wtf/wtf.log, so a bug report can come with evidence. Off by default.
+
+use crate::config::wtf_dir;
+use std::env;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+struct Logger {
+  /// API keys, replaced before anything reaches the file
+  secrets: Vec<String>,
+}
+
+static LOGGER: OnceLock<Option<Logger>> = OnceLock::new();
+
+/// Turns logging on when `WTF_LOG` is set (and not "0" or "off"). Every value in
+/// `secrets` is redacted from what gets written.
+pub fn init(secrets: Vec<String>) {
+  let enabled = env::var("WTF_LOG")
+    .map(|v| !v.is_empty() && v != "0" && !v.eq_ignore_ascii_case("off"))
+    .unwrap_or(false);
+
+  let logger = enabled.then(|| Logger {
+    secrets: secrets.into_iter().filter(|s| !s.is_empty()).collect(),
+  });
+  let _ = LOGGER.set(logger);
+}
+
+/// Appends one line to the log. Failing to write is never an error for the caller.
+pub fn log(message: &str) {
+  let Some(Some(logger)) = LOGGER.get() else {
+    return;
+  };
+
+  let mut message = message.replace('\n', "\\n");
+  for secret in &logger.secrets {
+    message = message.replace(secret.as_str(), "[redacted]");
+  }
+
+  let Ok(dir) = wtf_dir() else {
+    return;
+  };
+  let _ = std::fs::create_dir_all(&dir);
+
+  let Ok(mut file) = OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(dir.join("wtf.log"))
+  else {
+    return;
+  };
+
+  let now = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_secs())
+    .unwrap_or(0);
+  let _ = writeln!(file, "{} [{}] {}", now, std::process::id(), message);
+}
@@ -2,17 +2,33 @@ mod ai;
 mod commands;
 mod config;
 mod corrections;
+mod error;
 mod executor;
 mod history;
+mod hook;
+mod interrupt;
+mod learned;
+mod logging;
+mod manage;
+mod parse;
 mod path;
+mod rules;
+mod stats;
+mod symbols;
 mod ui;
+mod update;
 
 use clap::{Parser, Subcommand};
 use colored::Colorize;
-use config::UserConfig;
-use corrections::find_corrections;
-use executor::execute_command;
-use history::get_last_command;
+use config::{CustomTypo, MatchMode, UserConfig};
+use corrections::{
+  dropped_words, find_corrections, has_placeholders, heuristic_fix, learnable_fix,
+  normalize_unicode, retried_typo, sanitize_command, Correction, CorrectionSource, MAX_INPUT_CHARS,
+};
+use error::WtfError;
+use executor::{danger_reason, execute_command};
+use history::{get_last_command, get_recent_commands, HistoryOptions};
+use stats::AcceptanceStats;
 use ui::*;
 
 #[derive(Parser)]
@@ -34,6 +50,58 @@ struct Cli {
   /// Use AI to fix the command (requires Google Gemini API key)
   #[arg(long, global = true)]
   ai: bool,
+
+  /// Ignore history entries older than this (e.g. 5m, 1h)
+  #[arg(long, global = true, value_name = "DURATION", value_parser = history::parse_duration)]
+  since: Option<std::time::Duration>,
+
+  /// Run the suggestions one by one (most confident first) until one succeeds
+  #[arg(long, global = true)]
+  all: bool,
+
+  /// Run the Nth suggestion (1-based) without asking
+  #[arg(long, global = true, value_name = "N", conflicts_with = "all")]
+  run: Option<usize>,
+
+  /// Read the command to fix from stdin instead of history (needs --json or --run)
+  #[arg(long, global = true)]
+  stdin: bool,
+
+  /// Print the suggestions as JSON instead of prompting or running anything
+  #[arg(long, global = true)]
+  json: bool,
+
+  /// Use this config file instead of ~/.wtf/config.json (overrides WTF_CONFIG)
+  #[arg(long, global = true, value_name = "PATH")]
+  config: Option<std::path::PathBuf>,
+
+  /// Use the config of this profile (~/.wtf/profiles/<NAME>/config.json)
+  #[arg(long, global = true, value_name = "NAME", conflicts_with = "config")]
+  profile: Option<String>,
+
+  /// Only print suggestions and errors, no headers or tips (or set WTF_QUIET)
+  #[arg(short, long, global = true)]
+  quiet: bool,
+
+  /// Run the fix without asking only when it's the one suggestion at 100% confidence
+  #[arg(long, global = true)]
+  smart_auto: bool,
+
+  /// With --ai, exit with the error when AI fails instead of falling back to pattern matching
+  #[arg(long, global = true)]
+  no_fallback: bool,
+
+  /// Use AI and also show its one-line reason for the fix
+  #[arg(long, global = true)]
+  explain_ai: bool,
+
+  /// Fix the latest command that failed instead of the latest one (needs the hook, see `prefer_failed`)
+  #[arg(long, global = true)]
+  prefer_failed: bool,
+
+  /// Don't skip `wtf` invocations in history (for debugging what history recorded)
+  #[arg(long, global = true, hide = true)]
+  include_wtf: bool,
 }
 
 #[derive(Subcommand)]
@@ -45,6 +113,18 @@ enum Commands {
     wrong: String,
     /// The correct command
     correct: String,
+    /// Skip checking that the correct command exists on PATH
+    #[arg(long)]
+    no_verify: bool,
+    /// Replace the typo wherever it appears as a whole word, not just at the start
+    #[arg(long)]
+    word: bool,
+    /// Match the typo in any case (e.g. GIT or Git for git)
+    #[arg(long)]
+    ignore_case: bool,
+    /// Group the fix under a category (e.g. git), see active_categories
+    #[arg(long)]
+    category: Option<String>,
   },
 
   /// Remove a custom typo fix (alias: rm)
@@ -56,28 +136,61 @@ enum Commands {
 
   /// List all custom typos (alias: ls)
   #[command(name = "list", alias = "ls")]
-  List,
+  List {
+    /// Only show typos in this category
+    #[arg(long)]
+    category: Option<String>,
+  },
+
+  /// List built-in fixes, optionally filtered by a search term
+  Builtins {
+    /// Only show fixes whose typo, fix, or reason contains this text
+    filter: Option<String>,
+  },
+
+  /// Find custom typos that duplicate, conflict with, or are hidden by other fixes
+  AliasCheck,
 
   /// Clear all custom typos (alias: cls)
   #[command(name = "clear", alias = "cls")]
   Clear,
 
-  /// Show config file location (alias: cfg)
+  /// Show config file location, or the whole config with --json (alias: cfg)
   #[command(name = "config", alias = "cfg")]
-  Config,
+  Config {
+    #[command(subcommand)]
+    action: Option<ConfigAction>,
+    /// With --json, print API keys instead of "***"
+    #[arg(long, requires = "json")]
+    show_secrets: bool,
+  },
+
+  /// Open the config file in $EDITOR
+  #[command(name = "edit")]
+  Edit,
+
+  /// Add, edit, delete, and reorder custom typos in a terminal UI
+  Manage,
 
   /// Add the wrong command from history to custom fixes (alias: s)
   #[command(name = "save", alias = "s")]
   Save {
     /// The correct command
     correct: String,
+    /// Pick the wrong command from a list of recent commands
+    #[arg(long)]
+    pick: bool,
   },
 
-  /// Set Google AI API key for AI-powered fixing
+  /// Find typos you retyped by hand in history and offer to save them as custom fixes
+  Learn,
+
+  /// Set Google AI API key(s) for AI-powered fixing
   #[command(name = "set-api-key")]
   SetApiKey {
-    /// Your Google AI API key
-    api_key: String,
+    /// Your Google AI API key. Give several to fall back to the next one when rate limited
+    #[arg(required = true, num_args = 1..)]
+    api_keys: Vec<String>,
   },
 
   /// Add wtf to PATH environment variable (alias: i)
@@ -86,7 +199,28 @@ enum Commands {
 
   /// Remove wtf from PATH environment variable (alias: u)
   #[command(name = "uninstall", alias = "u")]
-  Uninstall,
+  Uninstall {
+    /// Also remove the bash history settings wtf added to ~/.bashrc (backed up to ~/.bashrc.bak)
+    #[arg(long)]
+    purge: bool,
+  },
+
+  /// Replace the installed wtf with the latest release from GitHub
+  #[command(name = "self-update")]
+  SelfUpdate,
+
+  /// List or create config profiles (see --profile)
+  Profile {
+    #[command(subcommand)]
+    action: ProfileAction,
+  },
+
+  /// Delete all wtf data in ~/.wtf (config, API key, stats, binary) and a --config file
+  Purge {
+    /// Save custom typos to this file before deleting
+    #[arg(long, value_name = "PATH")]
+    export: Option<std::path::PathBuf>,
+  },
 
   /// Enable or disable auto-mode (auto-run first suggestion) (alias: am)
   #[command(name = "auto-mode", alias = "am")]
@@ -110,15 +244,137 @@ enum Commands {
   #[command(name = "toggle-ai", alias = "tai")]
   ToggleAi,
 
+  /// Show the corrections for a command without running anything
+  #[command(name = "test")]
+  Test {
+    /// The command to correct
+    command: String,
+  },
+
+  /// Fix the given command instead of the last one in history
+  #[command(name = "fix")]
+  Fix {
+    /// The command to correct
+    command: String,
+  },
+
+  /// Print the chosen fix instead of running it, for use by shell wrappers
+  #[command(name = "eval")]
+  Eval,
+
+  /// Print shell functions that run fixes in the current shell: `wtf`, and `wtff` without asking
+  #[command(name = "hook")]
+  Hook {
+    /// Shell to generate the hook for: bash, zsh, fish or powershell (default: detect)
+    shell: Option<String>,
+  },
+
+  /// Re-run the last fixed command (alias: r)
+  #[command(name = "repeat", alias = "r")]
+  Repeat,
+
+  /// Show version information
+  Version {
+    /// Also show platform, shell, config path and AI setup (for bug reports)
+    #[arg(short, long)]
+    full: bool,
+  },
+
+  /// Show the welcome and install prompt again on the next bare `wtf`
+  #[command(name = "reset-first-run")]
+  ResetFirstRun,
+
   /// Configure bash history for real-time updates (Linux only) (alias: ch)
   #[command(name = "config-history", alias = "ch")]
   ConfigHistory,
+
+  /// Time find_corrections over sample typos, to catch slowdowns
+  #[command(hide = true)]
+  Bench {
+    /// How many times to run the whole sample set
+    #[arg(short = 'n', long, default_value_t = 50)]
+    iterations: usize,
+  },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+  /// Validate the config file without fixing anything; exits 1 on problems
+  Check,
+}
+
+#[derive(Subcommand)]
+enum ProfileAction {
+  /// List the profiles
+  List,
+  /// Create a profile with default settings
+  New {
+    /// Name of the profile
+    name: String,
+  },
+}
+
+// Set and not "0", like `WTF_QUIET=1`.
+fn env_flag(name: &str) -> bool {
+  std::env::var(name)
+    .map(|v| !v.is_empty() && v != "0")
+    .unwrap_or(false)
 }
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() {
   let cli = Cli::parse();
-  let mut user_config = UserConfig::load();
+  interrupt::install();
+  if cli.json {
+    // stdout carries the JSON, so everything meant for people goes to stderr
+    set_output_to_stderr(true);
+  }
+  init_colors();
+  set_quiet(cli.quiet || env_flag("WTF_QUIET"));
+  if let Some(path) = cli.config.clone() {
+    UserConfig::set_path_override(path);
+  } else if let Some(name) = &cli.profile {
+    use_profile(name, matches!(cli.command, Some(Commands::Profile { .. })));
+  }
+  let mut user_config = match UserConfig::try_load() {
+    Ok(config) => config,
+    // How a broken config gets fixed, so they still run; both report the error themselves
+    Err(_)
+      if matches!(
+        cli.command,
+        Some(Commands::Edit)
+          | Some(Commands::Config {
+            action: Some(ConfigAction::Check),
+            ..
+          })
+      ) =>
+    {
+      UserConfig::default()
+    }
+    Err(e) => {
+      display_error(&e.to_string());
+      eprintln!(
+        "{}",
+        "Fix it with 'wtf edit' (or by hand), then 'wtf config check'.".yellow()
+      );
+      std::process::exit(1);
+    }
+  };
+  symbols::init(user_config.ascii_only);
+  logging::init(
+    user_config
+      .get_google_api_keys()
+      .into_iter()
+      .chain(std::env::var("GOOGLE_API_KEY"))
+      .collect(),
+  );
+  // Not the arguments: `wtf set-api-key <key>` would put a key in the log
+  logging::log(&format!(
+    "wtf {} started, shell {}, config {}",
+    env!("CARGO_PKG_VERSION"),
+    hook::detect_shell(),
+    UserConfig::get_config_path_display()
+  ));
 
   if !user_config.first_run_complete && is_system_installed() {
     user_config.mark_first_run_complete();
@@ -129,33 +385,99 @@ async fn main() {
     handle_first_run_prompt(&mut user_config);
   }
 
+  let history_options = HistoryOptions {
+    since: cli.since,
+    include_wtf: cli.include_wtf,
+    prefer_failed: cli.prefer_failed || user_config.prefer_failed,
+    ..HistoryOptions::from_config(&user_config)
+  };
+
+  if cli.stdin && !cli.json && cli.run.is_none() {
+    display_error("--stdin needs --json or --run, since stdin can't also answer the prompt");
+    std::process::exit(1);
+  }
+  let stdin_command = cli.stdin.then(read_stdin_command);
+
+  // Computed once so the AI flow and its built-in fallback agree on whether to prompt
+  let auto_yes = cli.yes || user_config.auto_mode;
+  let auto_mode_only = user_config.auto_mode && !cli.yes && cli.run.is_none();
+  let smart_auto = cli.smart_auto || user_config.smart_auto;
+  let no_fallback = cli.no_fallback || user_config.ai_no_fallback;
+
   match cli.command {
-    Some(Commands::Add { wrong, correct }) => {
-      handle_add(&mut user_config, wrong, correct);
+    Some(Commands::Add {
+      wrong,
+      correct,
+      no_verify,
+      word,
+      ignore_case,
+      category,
+    }) => {
+      if word && has_placeholders(&correct) {
+        display_error("--word fixes replace the typo in place, so they can't use {args} or {N}");
+        std::process::exit(1);
+      }
+      let mut typo = CustomTypo::new(wrong, correct);
+      if word {
+        typo.match_mode = MatchMode::Word;
+      }
+      typo.ignore_case = ignore_case;
+      typo.category = category;
+      handle_add(&mut user_config, typo, no_verify, cli.yes);
     }
     Some(Commands::Remove { wrong }) => {
       handle_remove(&mut user_config, wrong);
     }
-    Some(Commands::List) => {
-      handle_list(&user_config);
+    Some(Commands::List { category }) => {
+      handle_list(&user_config, category.as_deref());
+    }
+    Some(Commands::Builtins { filter }) => {
+      handle_builtins(filter.as_deref());
+    }
+    Some(Commands::AliasCheck) => {
+      handle_alias_check(&mut user_config);
     }
     Some(Commands::Clear) => {
       handle_clear(&mut user_config);
     }
-    Some(Commands::Config) => {
-      handle_config();
+    Some(Commands::Config {
+      action: Some(ConfigAction::Check),
+      ..
+    }) => {
+      handle_config_check();
     }
-    Some(Commands::Save { correct }) => {
-      handle_save(&mut user_config, correct, cli.debug);
+    Some(Commands::Config { show_secrets, .. }) => {
+      handle_config(&user_config, cli.json, show_secrets);
     }
-    Some(Commands::SetApiKey { api_key }) => {
-      handle_set_api_key(api_key);
+    Some(Commands::Edit) => {
+      handle_edit(&user_config);
+    }
+    Some(Commands::Manage) => {
+      handle_manage(&mut user_config);
+    }
+    Some(Commands::Save { correct, pick }) => {
+      handle_save(&mut user_config, &history_options, correct, pick, cli.debug);
+    }
+    Some(Commands::Learn) => {
+      handle_learn(&mut user_config, &history_options, cli.yes);
+    }
+    Some(Commands::SetApiKey { api_keys }) => {
+      handle_set_api_key(&mut user_config, api_keys);
     }
     Some(Commands::Install) => {
       handle_install();
     }
-    Some(Commands::Uninstall) => {
-      handle_uninstall();
+    Some(Commands::SelfUpdate) => {
+      handle_self_update(cli.yes).await;
+    }
+    Some(Commands::Uninstall { purge }) => {
+      handle_uninstall(purge, cli.yes);
+    }
+    Some(Commands::Profile { action }) => {
+      handle_profile(action, cli.profile.as_deref());
+    }
+    Some(Commands::Purge { export }) => {
+      handle_purge(&user_config, export, cli.yes);
     }
     Some(Commands::AutoMode { enabled }) => {
       handle_auto_mode(&mut user_config, enabled);
@@ -169,17 +491,98 @@ async fn main() {
     Some(Commands::ToggleAi) => {
       handle_toggle_ai(&mut user_config);
     }
+    Some(Commands::Test { command }) => {
+      handle_test(&user_config, &command);
+    }
+    Some(Commands::Repeat) => {
+      handle_repeat(&user_config, cli.debug);
+    }
+    Some(Commands::Version { full }) => {
+      handle_version(&user_config, full);
+    }
+    Some(Commands::Bench { iterations }) => {
+      handle_bench(&user_config, iterations);
+    }
+    Some(Commands::ResetFirstRun) => {
+      handle_reset_first_run(&mut user_config);
+    }
     Some(Commands::ConfigHistory) => {
       handle_config_history();
     }
+    Some(Commands::Eval) => {
+      set_output_to_stderr(true);
+      init_colors();
+
+      let options = FixOptions {
+        auto_yes,
+        explicit_yes: cli.yes,
+        auto_mode_only,
+        debug: cli.debug,
+        eval: true,
+        run_all: false,
+        run_index: cli.run,
+        json: cli.json,
+        command: stdin_command.clone(),
+        smart_auto,
+        no_fallback,
+        explain_ai: cli.explain_ai,
+      };
+      dispatch_fix(
+        &options,
+        cli.ai || cli.explain_ai,
+        &mut user_config,
+        &history_options,
+      )
+      .await;
+    }
+    Some(Commands::Hook { shell }) => {
+      handle_hook(shell, &user_config);
+    }
+    Some(Commands::Fix { command }) => {
+      let options = FixOptions {
+        auto_yes,
+        explicit_yes: cli.yes,
+        auto_mode_only,
+        debug: cli.debug,
+        eval: false,
+        run_all: cli.all,
+        run_index: cli.run,
+        json: cli.json,
+        command: Some(command),
+        smart_auto,
+        no_fallback,
+        explain_ai: cli.explain_ai,
+      };
+      dispatch_fix(
+        &options,
+        cli.ai || cli.explain_ai,
+        &mut user_config,
+        &history_options,
+      )
+      .await;
+    }
     None => {
-      let auto_yes = cli.yes || user_config.auto_mode;
-
-      if cli.ai || user_config.ai_mode {
-        handle_ai_fix(auto_yes, cli.debug).await;
-      } else {
-        handle_fix(auto_yes, cli.debug, &user_config);
-      }
+      let options = FixOptions {
+        auto_yes,
+        explicit_yes: cli.yes,
+        auto_mode_only,
+        debug: cli.debug,
+        eval: false,
+        run_all: cli.all,
+        run_index: cli.run,
+        json: cli.json,
+        command: stdin_command.clone(),
+        smart_auto,
+        no_fallback,
+        explain_ai: cli.explain_ai,
+      };
+      dispatch_fix(
+        &options,
+        cli.ai || cli.explain_ai,
+        &mut user_config,
+        &history_options,
+      )
+      .await;
     }
   }
 }
@@ -204,9 +607,12 @@ fn handle_first_run_prompt(config: &mut UserConfig) {
   println!();
   println!(
     "{}",
-    "🎉 Welcome to WTF - Command Typo Fixer!"
-      .bright_cyan()
-      .bold()
+    format!(
+      "{} Welcome to WTF - Command Typo Fixer!",
+      symbols::welcome()
+    )
+    .bright_cyan()
+    .bold()
   );
   println!();
   println!(
@@ -217,11 +623,19 @@ fn handle_first_run_prompt(config: &mut UserConfig) {
   println!();
   println!(
     "{}",
-    "  • You can run 'wtf install' later to install".dimmed()
+    format!(
+      "  {} You can run 'wtf install' later to install",
+      symbols::bullet()
+    )
+    .dimmed()
   );
   println!(
     "{}",
-    "  • You can run 'wtf uninstall' to remove it".dimmed()
+    format!(
+      "  {} You can run 'wtf uninstall' to remove it",
+      symbols::bullet()
+    )
+    .dimmed()
   );
   println!();
   print!("{} [Y/n]: ", "Install globally?".bright_cyan());
@@ -249,7 +663,7 @@ fn handle_first_run_prompt(config: &mut UserConfig) {
         println!();
         println!(
           "{} {}",
-          "✓".bright_green(),
+          symbols::ok().bright_green(),
           "Installation complete!".bright_green()
         );
         println!();
@@ -257,7 +671,11 @@ fn handle_first_run_prompt(config: &mut UserConfig) {
         println!();
         println!(
           "{}",
-          "💡 Tip: Restart your terminal for PATH changes to take effect".yellow()
+          format!(
+            "{} Tip: Restart your terminal for PATH changes to take effect",
+            symbols::tip()
+          )
+          .yellow()
         );
         println!();
       }
@@ -293,7 +711,7 @@ fn should_configure_bash_history() -> bool {
     return false;
   }
 
-  let home = match dirs::home_dir() {
+  let home = match config::home_dir().ok() {
     Some(h) => h,
     None => return false,
   };
@@ -340,10 +758,10 @@ fn configure_bash_history() {
     return;
   }
 
-  let home = match dirs::home_dir() {
-    Some(h) => h,
-    None => {
-      println!("{}", "Could not find home directory.".red());
+  let home = match config::home_dir() {
+    Ok(h) => h,
+    Err(e) => {
+      display_error(&e.to_string());
       return;
     }
   };
@@ -376,7 +794,7 @@ fn configure_bash_history() {
     println!();
     println!(
       "{} {}",
-      "✓".bright_green(),
+      symbols::ok().bright_green(),
       "Bash history is already configured!".bright_green()
     );
     println!();
@@ -388,7 +806,12 @@ fn configure_bash_history() {
   }
 
   println!();
-  println!("{}", "📝 Bash History Configuration".bright_cyan().bold());
+  println!(
+    "{}",
+    format!("{} Bash History Configuration", symbols::note())
+      .bright_cyan()
+      .bold()
+  );
   println!();
   println!(
     "{}",
@@ -411,14 +834,17 @@ fn configure_bash_history() {
     let mut new_content = bashrc_content.clone();
 
     new_content.push_str("\n\n");
-    new_content.push_str("# WTF - Command Typo Fixer: Enable real-time history\n");
+    new_content.push_str(BASH_HISTORY_HEADER);
+    new_content.push('\n');
 
     if !has_histappend {
-      new_content.push_str("shopt -s histappend\n");
+      new_content.push_str(BASH_HISTORY_LINES[0]);
+      new_content.push('\n');
     }
 
     if !has_prompt_command {
-      new_content.push_str("PROMPT_COMMAND='history -a'\n");
+      new_content.push_str(BASH_HISTORY_LINES[1]);
+      new_content.push('\n');
     }
 
     match fs::write(&bashrc_path, new_content) {
@@ -426,7 +852,7 @@ fn configure_bash_history() {
         println!();
         println!(
           "{} {}",
-          "✓".bright_green(),
+          symbols::ok().bright_green(),
           "Bash configuration updated!".bright_green()
         );
         println!();
@@ -461,116 +887,1111 @@ fn configure_bash_history() {
   }
 }
 
-fn handle_fix(auto_yes: bool, debug: bool, user_config: &UserConfig) {
-  match get_last_command() {
+// What configure_bash_history appends to .bashrc, so uninstall --purge can take it out again
+#[cfg(not(target_os = "windows"))]
+const BASH_HISTORY_HEADER: &str = "# WTF - Command Typo Fixer: Enable real-time history";
+#[cfg(not(target_os = "windows"))]
+const BASH_HISTORY_LINES: [&str; 2] = ["shopt -s histappend", "PROMPT_COMMAND='history -a'"];
+
+/// Removes the block configure_bash_history added, returning the new content and the
+/// removed lines, or None if there isn't one. Only the lines directly under wtf's header
+/// go; the same settings elsewhere stay.
+#[cfg(not(target_os = "windows"))]
+fn strip_bash_history_block(content: &str) -> Option<(String, Vec<&str>)> {
+  let lines: Vec<&str> = content.lines().collect();
+  let start = lines.iter().position(|line| *line == BASH_HISTORY_HEADER)?;
+
+  let mut end = start + 1;
+  for expected in BASH_HISTORY_LINES {
+    if lines.get(end) == Some(&expected) {
+      end += 1;
+    }
+  }
+
+  // The two blank lines written before the header
+  let mut first = start;
+  while first > start.saturating_sub(2) && lines[first - 1].is_empty() {
+    first -= 1;
+  }
+
+  let mut kept: Vec<&str> = lines[..first].to_vec();
+  kept.extend_from_slice(&lines[end..]);
+
+  let mut stripped = kept.join("\n");
+  if !stripped.is_empty() && content.ends_with('\n') {
+    stripped.push('\n');
+  }
+  Some((stripped, lines[start..end].to_vec()))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn revert_bash_history(yes: bool) -> Result<(), String> {
+  use std::fs;
+
+  let home = config::home_dir().map_err(|e| e.to_string())?;
+  let bashrc_path = home.join(".bashrc");
+
+  let content = match fs::read_to_string(&bashrc_path) {
+    Ok(content) => content,
+    Err(_) => {
+      display_info(&format!("{} No ~/.bashrc to clean up.", symbols::info()));
+      return Ok(());
+    }
+  };
+
+  let Some((stripped, removed)) = strip_bash_history_block(&content) else {
+    display_info(&format!(
+      "{} ~/.bashrc has no history settings added by wtf.",
+      symbols::info()
+    ));
+    return Ok(());
+  };
+
+  println!("{}", "wtf added this to ~/.bashrc:".bright_cyan());
+  for line in removed {
+    println!("  {}", line.dimmed());
+  }
+  println!();
+
+  if !yes && !prompt_yes_no("Remove it?", false) {
+    println!("{}", "Left ~/.bashrc unchanged.".yellow());
+    return Ok(());
+  }
+
+  let backup_path = home.join(".bashrc.bak");
+  fs::write(&backup_path, &content).map_err(|e| format!("Failed to back up .bashrc: {}", e))?;
+  fs::write(&bashrc_path, stripped).map_err(|e| format!("Failed to update .bashrc: {}", e))?;
+
+  println!(
+    "{} Removed wtf's history settings from ~/.bashrc (backup: {})",
+    symbols::ok().bright_green(),
+    backup_path.display()
+  );
+  Ok(())
+}
+
+/// Exit code when there is nothing to run, so scripts and shell wrappers can tell it apart from errors
+const EXIT_NO_SUGGESTIONS: i32 = 2;
+
+fn exit_code(error: &WtfError) -> i32 {
+  match error {
+    WtfError::NoCorrections(_) => EXIT_NO_SUGGESTIONS,
+    _ => 1,
+  }
+}
+
+struct FixOptions {
+  auto_yes: bool,
+  /// `--yes` itself: the only thing that skips typing `yes` for a dangerous fix
+  explicit_yes: bool,
+  /// Auto-run comes from auto-mode rather than an explicit `--yes`, so the confidence threshold applies
+  auto_mode_only: bool,
+  debug: bool,
+  /// Print the chosen fix to stdout instead of running it (for shell wrappers)
+  eval: bool,
+  /// Try every suggestion until one exits successfully
+  run_all: bool,
+  /// `--run N`: pick this suggestion (1-based) instead of prompting
+  run_index: Option<usize>,
+  /// Print the suggestions as JSON and stop
+  json: bool,
+  /// Correct this command instead of reading it from history
+  command: Option<String>,
+  /// Skip the prompt when there is exactly one suggestion at 100% confidence
+  smart_auto: bool,
+  /// AI failures are fatal instead of falling back to pattern matching
+  no_fallback: bool,
+  /// Ask the AI why, and show its answer under the suggestion
+  explain_ai: bool,
+}
+
+async fn dispatch_fix(
+  options: &FixOptions,
+  ai: bool,
+  user_config: &mut UserConfig,
+  history_options: &HistoryOptions,
+) {
+  // The AI flow talks to the user as it goes, which doesn't fit a single JSON answer
+  if (ai || user_config.ai_mode) && !options.json {
+    handle_ai_fix(options, user_config, history_options).await;
+  } else {
+    handle_fix(options, user_config, history_options);
+  }
+}
+
+fn resolve_command(
+  options: &FixOptions,
+  history_options: &HistoryOptions,
+) -> Result<String, WtfError> {
+  let result = match &options.command {
+    Some(cmd) => Ok(cmd.clone()),
+    None => get_last_command(history_options),
+  }
+  .map(|raw| {
+    let cmd = sanitize_command(&raw);
+    if cmd != raw {
+      logging::log(&format!("stripped control characters from {:?}", raw));
+      if options.debug {
+        display_debug(&format!("Raw command: {:?}", raw));
+      }
+    }
+    cmd
+  });
+
+  match &result {
+    Ok(cmd) => logging::log(&format!("command to fix: {}", cmd)),
+    Err(e) => logging::log(&format!("no command to fix: {}", e)),
+  }
+  result
+}
+
+fn log_corrections(corrections: &[Correction]) {
+  let listed: Vec<String> = corrections
+    .iter()
+    .map(|c| format!("{} ({}, {:.2})", c.fixed_cmd, c.source.name(), c.confidence))
+    .collect();
+  logging::log(&format!("corrections: [{}]", listed.join(", ")));
+}
+
+fn handle_fix(
+  options: &FixOptions,
+  user_config: &mut UserConfig,
+  history_options: &HistoryOptions,
+) {
+  match resolve_command(options, history_options) {
     Ok(last_cmd) => {
-      if debug {
-        println!("Last command: {}", last_cmd);
+      if options.debug {
+        display_debug(&format!("Last command: {}", last_cmd));
+      }
+
+      let length = last_cmd.chars().count();
+      if length > MAX_INPUT_CHARS {
+        display_error(&format!(
+          "Command is too long to correct ({} characters, max {})",
+          length, MAX_INPUT_CHARS
+        ));
+        std::process::exit(1);
+      }
+
+      // Read once: ranking and recording use the same counts
+      let mut stats = AcceptanceStats::load();
+
+      if options.json {
+        let corrections = find_corrections(&last_cmd, user_config, &stats).unwrap_or_default();
+        log_corrections(&corrections);
+        let auto_index = auto_choice(&last_cmd, options, &corrections, user_config);
+        print_json_corrections(&last_cmd, &corrections, auto_index);
+        if corrections.is_empty() {
+          std::process::exit(EXIT_NO_SUGGESTIONS);
+        }
+        return;
       }
 
-      match find_corrections(&last_cmd, user_config) {
+      let found = find_corrections(&last_cmd, user_config, &stats);
+      log_corrections(found.as_deref().unwrap_or_default());
+
+      match found {
         Some(corrections) => {
           display_corrections(&last_cmd, &corrections);
 
-          let selected = if auto_yes {
+          stats.record_shown(corrections.iter().map(|c| c.fixed_cmd.as_str()));
+          let _ = stats.save();
+
+          // Auto-mode runs the top-ranked fix, which isn't always the most confident one
+          let top = corrections[0].confidence;
+          let threshold = user_config.auto_confidence_threshold;
+
+          if options.auto_mode_only && top < threshold {
+            display_auto_skipped(top, threshold);
+            std::process::exit(EXIT_NO_SUGGESTIONS);
+          }
+
+          if options.run_all {
+            run_all_fixes(&last_cmd, &corrections, user_config, options, &mut stats);
+            return;
+          }
+
+          let selected = if let Some(n) = options.run_index {
+            if n == 0 || n > corrections.len() {
+              display_error(&format!(
+                "--run {} is out of range: there are {} suggestion(s)",
+                n,
+                corrections.len()
+              ));
+              std::process::exit(1);
+            }
+            n - 1
+          } else if options.auto_yes || runs_without_asking(options, &corrections, user_config) {
             0
           } else {
             match prompt_selection(corrections.len()) {
               Some(idx) => idx,
               None => {
-                println!("{}", "Cancelled.".yellow());
+                display_cancelled();
+                if options.eval {
+                  std::process::exit(1);
+                }
                 return;
               }
             }
           };
 
-          let cmd_to_run = &corrections[selected].fixed_cmd;
-          display_success(cmd_to_run);
+          let correction = &corrections[selected];
+          // A fix outside exec_allowlist is only shown, before any prompt, stats or learning
+          ensure_exec_allowed(&correction.fixed_cmd, user_config);
 
-          if let Err(e) = execute_command(cmd_to_run) {
-            display_error(&e);
-            std::process::exit(1);
+          let dropped = dropped_words(&last_cmd, &correction.fixed_cmd);
+          if !dropped.is_empty() && !confirm_dropped_words(&dropped) {
+            display_cancelled();
+            if options.eval {
+              std::process::exit(1);
+            }
+            return;
+          }
+
+          let non_interactive = options.auto_yes
+            || options.run_index.is_some()
+            || runs_without_asking(options, &corrections, user_config);
+          // Picking a fix with --run N or auto-mode isn't agreeing to what it does; --yes is
+          if !options.explicit_yes {
+            if let Some(reason) = danger_reason(&correction.fixed_cmd) {
+              if !confirm_dangerous(&correction.fixed_cmd, reason) {
+                display_cancelled();
+                if options.eval {
+                  std::process::exit(1);
+                }
+                return;
+              }
+            }
+          }
+
+          stats.record_accepted(&correction.fixed_cmd);
+          let _ = stats.save();
+
+          if let Some((wrong, correct)) = learnable_fix(&last_cmd, correction) {
+            maybe_learn_fix(user_config, wrong, correct, non_interactive);
+          }
+          if correction.source == CorrectionSource::Fuzzy {
+            remember_in_directory(&last_cmd, &correction.fixed_cmd, user_config);
+          }
+
+          if options.eval {
+            emit_fix(&correction.fixed_cmd, user_config);
+          } else {
+            run_fix(&correction.fixed_cmd, user_config, options.debug);
           }
         }
         None => {
           display_no_suggestions(&last_cmd);
+          std::process::exit(EXIT_NO_SUGGESTIONS);
         }
       }
     }
     Err(e) => {
-      display_error(&e);
+      display_error(&e.to_string());
       std::process::exit(1);
     }
   }
 }
 
-fn handle_add(config: &mut UserConfig, wrong: String, correct: String) {
-  let builtin_fixes = commands::get_common_fixes();
-  let is_builtin = builtin_fixes
-    .iter()
-    .any(|(typo, fix)| *typo == wrong || fix.0 == correct);
+// `smart_auto` runs an unambiguous fix without asking, and `safe_commands` a lone harmless one.
+fn runs_without_asking(
+  options: &FixOptions,
+  corrections: &[Correction],
+  user_config: &UserConfig,
+) -> bool {
+  let [only] = corrections else {
+    return false;
+  };
 
-  if is_builtin {
-    config.add_from_builtin(wrong.clone(), correct.clone());
-    display_info("ℹ This typo is already in built-in database, adding to your custom list.");
+  let certain = options.smart_auto && only.confidence >= 1.0;
+  let safe = user_config.is_safe_command(&only.fixed_cmd);
+  (certain || safe) && danger_reason(&only.fixed_cmd).is_none()
+}
+
+// Which suggestion a run without `--json` would execute with no prompt, if any.
+fn auto_choice(
+  cmd: &str,
+  options: &FixOptions,
+  corrections: &[Correction],
+  user_config: &UserConfig,
+) -> Option<usize> {
+  let top = corrections.first()?.confidence;
+  let index = if options.auto_mode_only && top < user_config.auto_confidence_threshold {
+    return None;
+  } else if let Some(n) = options.run_index {
+    (1..=corrections.len()).contains(&n).then(|| n - 1)?
+  } else if options.auto_yes || runs_without_asking(options, corrections, user_config) {
+    0
   } else {
-    config.add_typo(wrong.clone(), correct.clone());
-  }
+    return None;
+  };
 
-  if let Err(e) = config.save() {
-    display_error(&format!("Failed to save config: {}", e));
-    std::process::exit(1);
-  }
+  // Dropped words get asked about even with --yes, and dangerous fixes without it,
+  // so that run would stop at a prompt
+  let fixed_cmd = &corrections.get(index)?.fixed_cmd;
+  (user_config.is_exec_allowed(fixed_cmd)
+    && dropped_words(cmd, fixed_cmd).is_empty()
+    && (options.explicit_yes || danger_reason(fixed_cmd).is_none()))
+  .then_some(index)
+}
 
-  display_added(&wrong, &correct);
+fn print_json_corrections(cmd: &str, corrections: &[Correction], auto_index: Option<usize>) {
+  let suggestions: Vec<serde_json::Value> = corrections
+    .iter()
+    .map(|c| {
+      serde_json::json!({
+        "command": c.fixed_cmd,
+        "reason": c.reason,
+        "confidence": c.confidence,
+        "source": c.source.name(),
+      })
+    })
+    .collect();
+
+  let output = serde_json::json!({
+    "command": cmd,
+    "suggestions": suggestions,
+    "would_auto_run": auto_index.is_some(),
+    "auto_index": auto_index,
+  });
+  println!("{}", output);
 }
 
-fn handle_remove(config: &mut UserConfig, wrong: String) {
-  if config.remove_typo(&wrong) {
-    if let Err(e) = config.save() {
-      display_error(&format!("Failed to save config: {}", e));
-      std::process::exit(1);
-    }
-    display_removed(&wrong);
-  } else {
-    display_error(&format!("Typo '{}' not found in custom list", wrong));
+// `--stdin`: the whole input is the command, so tools don't have to escape it as an argument.
+fn read_stdin_command() -> String {
+  use std::io::Read;
+
+  let mut input = String::new();
+  if let Err(e) = std::io::stdin().read_to_string(&mut input) {
+    display_error(&format!("Failed to read command from stdin: {}", e));
     std::process::exit(1);
   }
-}
 
-fn handle_list(config: &UserConfig) {
-  display_custom_typos(&config.custom_typos);
+  let cmd = input.trim();
+  if cmd.is_empty() {
+    display_error("No command on stdin");
+    std::process::exit(1);
+  }
+  cmd.to_string()
 }
 
-fn handle_clear(config: &mut UserConfig) {
-  let count = config.custom_typos.len();
-  config.custom_typos.clear();
+fn maybe_learn_fix(config: &mut UserConfig, wrong: String, correct: String, auto_yes: bool) {
+  let remember = if config.learn_fuzzy {
+    true
+  } else if auto_yes {
+    false
+  } else {
+    prompt_yes_no("Remember this fix?", false)
+  };
 
-  if let Err(e) = config.save() {
-    display_error(&format!("Failed to save config: {}", e));
-    std::process::exit(1);
+  if !remember {
+    return;
   }
 
-  println!("{} Cleared {} custom typo(s)", "✓".bright_green(), count);
-}
+  config.add_typo(CustomTypo::new(wrong.clone(), correct.clone()));
 
-fn handle_config() {
-  println!("{}", "Config file location:".bright_cyan());
-  println!("  {}", UserConfig::get_config_path_display().bright_white());
+  match config.save() {
+    Ok(_) => display_added(&wrong, &correct),
+    Err(e) => eprintln!(
+      "{}",
+      format!("Warning: Failed to save config: {}", e).yellow()
+    ),
+  }
 }
 
-fn handle_auto_mode(config: &mut UserConfig, enabled: bool) {
-  config.set_auto_mode(enabled);
+// `remember_per_directory`: the next time this exact command is fixed in this project,
+// the same fix is offered first.
+fn remember_in_directory(cmd: &str, fixed_cmd: &str, config: &UserConfig) {
+  if !config.remember_per_directory || cmd == fixed_cmd {
+    return;
+  }
 
-  if let Err(e) = config.save() {
-    display_error(&format!("Failed to save config: {}", e));
-    std::process::exit(1);
+  if let Err(e) = learned::remember_fix(cmd, fixed_cmd) {
+    eprintln!(
+      "{}",
+      format!("Warning: Failed to remember fix: {}", e).yellow()
+    );
   }
+}
 
-  if enabled {
+fn ensure_exec_allowed(cmd: &str, config: &UserConfig) {
+  if !config.is_exec_allowed(cmd) {
+    display_not_allowed(cmd);
+    std::process::exit(1);
+  }
+}
+
+// Hands the fix to a shell wrapper, which evals it in the current shell.
+fn emit_fix(cmd: &str, config: &UserConfig) {
+  ensure_exec_allowed(cmd, config);
+
+  if let Err(e) = config::save_last_fix(cmd) {
+    eprintln!(
+      "{}",
+      format!("Warning: Failed to record last fix: {}", e).yellow()
+    );
+  }
+
+  println!("{}", cmd);
+}
+
+fn run_fix(cmd: &str, config: &UserConfig, debug: bool) {
+  ensure_exec_allowed(cmd, config);
+  display_success(cmd);
+
+  if let Err(e) = config::save_last_fix(cmd) {
+    eprintln!(
+      "{}",
+      format!("Warning: Failed to record last fix: {}", e).yellow()
+    );
+  }
+
+  run_and_exit_on_failure(cmd, debug);
+}
+
+// `--all`: runs the fixes most-confident first and stops at the first that succeeds.
+fn run_all_fixes(
+  last_cmd: &str,
+  corrections: &[Correction],
+  config: &UserConfig,
+  options: &FixOptions,
+  stats: &mut AcceptanceStats,
+) {
+  let mut ordered: Vec<&Correction> = corrections.iter().collect();
+  ordered.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+
+  if !options.auto_yes
+    && !prompt_yes_no(
+      &format!("Try up to {} fixes until one succeeds?", ordered.len()),
+      true,
+    )
+  {
+    display_cancelled();
+    return;
+  }
+
+  let mut failed = Vec::new();
+
+  for correction in ordered {
+    let cmd = &correction.fixed_cmd;
+
+    if !config.is_exec_allowed(cmd) {
+      display_not_allowed(cmd);
+      failed.push((cmd.clone(), None));
+      continue;
+    }
+
+    let dropped = dropped_words(last_cmd, cmd);
+    if !dropped.is_empty() && !confirm_dropped_words(&dropped) {
+      failed.push((cmd.clone(), None));
+      continue;
+    }
+
+    if let Some(reason) = danger_reason(cmd).filter(|_| !options.explicit_yes) {
+      if !confirm_dangerous(cmd, reason) {
+        failed.push((cmd.clone(), None));
+        continue;
+      }
+    }
+
+    display_success(cmd);
+
+    match execute_command(cmd, options.debug) {
+      Ok(0) => {
+        stats.record_accepted(cmd);
+        let _ = stats.save();
+        let _ = config::save_last_fix(cmd);
+        display_run_all_summary(&failed, Some(cmd));
+        return;
+      }
+      Ok(code) => failed.push((cmd.clone(), Some(code))),
+      Err(e) => {
+        display_error(&e);
+        failed.push((cmd.clone(), None));
+      }
+    }
+  }
+
+  display_run_all_summary(&failed, None);
+  std::process::exit(1);
+}
+
+// Exits with the command's own exit code when it fails, so callers see it.
+fn run_and_exit_on_failure(cmd: &str, debug: bool) {
+  match execute_command(cmd, debug) {
+    Ok(0) => display_run_result(0),
+    Ok(code) => {
+      display_run_result(code);
+      std::process::exit(code);
+    }
+    Err(e) => {
+      display_error(&e);
+      std::process::exit(1);
+    }
+  }
+}
+
+fn handle_hook(shell: Option<String>, config: &UserConfig) {
+  let shell = shell.unwrap_or_else(hook::detect_shell);
+
+  match hook::hook_script(&shell, config.prefer_failed) {
+    Ok(script) => print!("{}", script),
+    Err(e) => {
+      display_error(&e);
+      std::process::exit(1);
+    }
+  }
+}
+
+fn handle_test(config: &UserConfig, cmd: &str) {
+  let corrections = find_corrections(cmd, config, &AcceptanceStats::load()).unwrap_or_default();
+  display_test_results(cmd, &corrections);
+}
+
+fn handle_repeat(config: &UserConfig, debug: bool) {
+  match config::load_last_fix() {
+    Ok(cmd) => {
+      ensure_exec_allowed(&cmd, config);
+      display_success(&cmd);
+      run_and_exit_on_failure(&cmd, debug);
+    }
+    Err(e) => {
+      display_error(&e.to_string());
+      std::process::exit(exit_code(&e));
+    }
+  }
+}
+
+fn handle_add(config: &mut UserConfig, typo: CustomTypo, no_verify: bool, yes: bool) {
+  let existing = config
+    .custom_typos
+    .iter()
+    .find(|t| t.wrong == typo.wrong && t.correct != typo.correct);
+  if let Some(existing) = existing {
+    let question = format!(
+      "Replace existing fix '{} {} {}' with '{} {} {}'?",
+      existing.wrong,
+      symbols::arrow(),
+      existing.correct,
+      typo.wrong,
+      symbols::arrow(),
+      typo.correct
+    );
+    if !yes && !prompt_yes_no(&question, false) {
+      display_cancelled();
+      return;
+    }
+  }
+
+  if !no_verify {
+    if let Some(program) = typo.correct.split_whitespace().next() {
+      if !path::command_exists(program) {
+        println!(
+          "{}",
+          format!(
+            "{} '{}' was not found on PATH. It may be a typo (or a shell builtin/alias).",
+            symbols::warn(),
+            program
+          )
+          .yellow()
+        );
+        println!("{}", "Use --no-verify to skip this check.".dimmed());
+      }
+    }
+  }
+
+  let builtin_fixes = commands::get_common_fixes();
+  let is_builtin = builtin_fixes
+    .iter()
+    .any(|(wrong, fix)| *wrong == typo.wrong || fix.0 == typo.correct);
+
+  let (wrong, correct) = (typo.wrong.clone(), typo.correct.clone());
+
+  if is_builtin {
+    config.add_from_builtin(typo);
+    display_info(&format!(
+      "{} This typo is already in built-in database, adding to your custom list.",
+      symbols::info()
+    ));
+  } else {
+    config.add_typo(typo);
+  }
+
+  if let Err(e) = config.save() {
+    display_error(&format!("Failed to save config: {}", e));
+    std::process::exit(1);
+  }
+
+  display_added(&wrong, &correct);
+}
+
+fn handle_remove(config: &mut UserConfig, wrong: String) {
+  if config.remove_typo(&wrong) {
+    if let Err(e) = config.save() {
+      display_error(&format!("Failed to save config: {}", e));
+      std::process::exit(1);
+    }
+    display_removed(&wrong);
+  } else {
+    display_error(&format!("Typo '{}' not found in custom list", wrong));
+    std::process::exit(1);
+  }
+}
+
+fn handle_list(config: &UserConfig, category: Option<&str>) {
+  let Some(category) = category else {
+    display_custom_typos(&config.custom_typos);
+    return;
+  };
+
+  let typos: Vec<CustomTypo> = config
+    .custom_typos
+    .iter()
+    .filter(|typo| {
+      typo
+        .category
+        .as_deref()
+        .is_some_and(|c| c.eq_ignore_ascii_case(category))
+    })
+    .cloned()
+    .collect();
+  display_custom_typos(&typos);
+}
+
+fn handle_manage(config: &mut UserConfig) {
+  use std::io::IsTerminal;
+
+  if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+    display_error("wtf manage needs a terminal; use wtf add/remove/list instead");
+    std::process::exit(1);
+  }
+
+  match manage::run(config.custom_typos.clone()) {
+    Ok(manage::Outcome::Save(typos)) => {
+      config.custom_typos = typos;
+      if let Err(e) = config.save() {
+        display_error(&format!("Failed to save config: {}", e));
+        std::process::exit(1);
+      }
+      println!(
+        "{} Saved {} custom typo(s)",
+        symbols::ok().bright_green(),
+        config.custom_typos.len()
+      );
+    }
+    Ok(manage::Outcome::Discard) => display_cancelled(),
+    Err(e) => {
+      display_error(&e);
+      std::process::exit(1);
+    }
+  }
+}
+
+fn handle_builtins(filter: Option<&str>) {
+  let fixes = commands::get_common_fixes();
+  let matches: Vec<_> = match filter {
+    Some(term) => {
+      let term = term.to_lowercase();
+      fixes
+        .into_iter()
+        .filter(|(wrong, (correct, reason, _))| {
+          wrong.contains(&term) || correct.contains(&term) || reason.to_lowercase().contains(&term)
+        })
+        .collect()
+    }
+    None => fixes,
+  };
+  display_builtin_fixes(&matches, filter);
+}
+
+fn handle_alias_check(config: &mut UserConfig) {
+  use config::TypoIssue;
+  use std::io::IsTerminal;
+
+  let issues = config.check_typos();
+  display_typo_issues(&config.custom_typos, &issues);
+
+  let redundant: Vec<usize> = issues
+    .iter()
+    .filter(|(_, issue)| matches!(issue, TypoIssue::Redundant))
+    .map(|(i, _)| *i)
+    .collect();
+  if redundant.is_empty() || !std::io::stdin().is_terminal() {
+    return;
+  }
+
+  let question = format!("Remove {} redundant custom typo(s)?", redundant.len());
+  if !prompt_yes_no(&question, false) {
+    return;
+  }
+
+  // By index, since a shadowed duplicate may share the typo
+  let mut index = 0;
+  config.custom_typos.retain(|_| {
+    index += 1;
+    !redundant.contains(&(index - 1))
+  });
+
+  if let Err(e) = config.save() {
+    display_error(&format!("Failed to save config: {}", e));
+    std::process::exit(1);
+  }
+  println!(
+    "{} Removed {} redundant custom typo(s)",
+    symbols::ok().bright_green(),
+    redundant.len()
+  );
+}
+
+// `--profile`: every config read and write goes to the profile from here on.
+// `wtf profile` itself may name a profile that doesn't exist yet.
+fn use_profile(name: &str, managing_profiles: bool) {
+  let path = match config::profile_config_path(name) {
+    Ok(path) => path,
+    Err(e) => {
+      display_error(&e.to_string());
+      std::process::exit(1);
+    }
+  };
+
+  if !path.exists() && !managing_profiles {
+    display_error(&format!(
+      "Profile '{}' not found. Create it with: wtf profile new {}",
+      name, name
+    ));
+    std::process::exit(1);
+  }
+
+  UserConfig::set_path_override(path);
+}
+
+fn handle_profile(action: ProfileAction, active: Option<&str>) {
+  match action {
+    ProfileAction::List => {
+      let profiles = match config::list_profiles() {
+        Ok(profiles) => profiles,
+        Err(e) => {
+          display_error(&e.to_string());
+          std::process::exit(1);
+        }
+      };
+
+      if profiles.is_empty() {
+        println!("{}", "No profiles yet.".yellow());
+        println!();
+        println!("{}", "Create one with:".dimmed());
+        println!("  wtf profile new work");
+        return;
+      }
+
+      println!("{}", "Profiles:".bright_cyan().bold());
+      println!();
+      for name in &profiles {
+        if Some(name.as_str()) == active {
+          println!("  {} {}", name.bright_green(), "(active)".dimmed());
+        } else {
+          println!("  {}", name);
+        }
+      }
+      println!();
+      println!(
+        "{}",
+        "Without --profile, wtf uses ~/.wtf/config.json".dimmed()
+      );
+    }
+    ProfileAction::New { name } => match config::create_profile(&name) {
+      Ok(path) => {
+        println!(
+          "{} Created profile '{}' at {}",
+          symbols::ok().bright_green(),
+          name,
+          path.display()
+        );
+        println!(
+          "{}",
+          format!("Use it with: wtf --profile {}", name).dimmed()
+        );
+      }
+      Err(e) => {
+        display_error(&e.to_string());
+        std::process::exit(1);
+      }
+    },
+  }
+}
+
+// A spread of what `find_corrections` sees: builtin typos, fuzzy matches, rules,
+// chains, substitutions, and commands with nothing to fix.
+const BENCH_COMMANDS: &[&str] = &[
+  "gti status",
+  "gut comit -m fix",
+  "carg biuld --release",
+  "dokcer ps -a",
+  "kubectl gt pods",
+  "sl -la",
+  "npm isntall express",
+  "apt isntall curl",
+  "git commit -m fix the bug",
+  "echo $PAHT",
+  "make 2>1",
+  "mkdir foo && gti add foo",
+  "echo $(gti rev-parse HEAD)",
+  "python script.py",
+  "ls -la",
+  "cargo build",
+  "some-unknown-tool --flag value",
+];
+
+fn handle_bench(config: &UserConfig, iterations: usize) {
+  use std::time::{Duration, Instant};
+
+  let iterations = iterations.max(1);
+  let mut timings: Vec<Duration> = Vec::with_capacity(iterations * BENCH_COMMANDS.len());
+
+  let stats = AcceptanceStats::load();
+  let started = Instant::now();
+  for _ in 0..iterations {
+    for cmd in BENCH_COMMANDS {
+      let start = Instant::now();
+      let _ = find_corrections(cmd, config, &stats);
+      timings.push(start.elapsed());
+    }
+  }
+  let total = started.elapsed();
+
+  timings.sort();
+  let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+
+  println!(
+    "{} {} commands x {} iterations in {:.1} ms",
+    "Bench:".bright_cyan().bold(),
+    BENCH_COMMANDS.len(),
+    iterations,
+    ms(total)
+  );
+  println!("  min    {:>8.3} ms", ms(timings[0]));
+  println!("  median {:>8.3} ms", ms(timings[timings.len() / 2]));
+  println!("  max    {:>8.3} ms", ms(timings[timings.len() - 1]));
+}
+
+fn handle_clear(config: &mut UserConfig) {
+  let count = config.custom_typos.len();
+  config.custom_typos.clear();
+
+  if let Err(e) = config.save() {
+    display_error(&format!("Failed to save config: {}", e));
+    std::process::exit(1);
+  }
+
+  println!(
+    "{} Cleared {} custom typo(s)",
+    symbols::ok().bright_green(),
+    count
+  );
+}
+
+fn handle_config(config: &UserConfig, json: bool, show_secrets: bool) {
+  if json {
+    let mut config = config.clone();
+    if !show_secrets {
+      let redact = |_: String| "***".to_string();
+      config.google_api_key = config.google_api_key.map(redact);
+      config.google_api_keys = config.google_api_keys.into_iter().map(redact).collect();
+    }
+    match serde_json::to_string_pretty(&config) {
+      Ok(output) => println!("{}", output),
+      Err(e) => {
+        display_error(&format!("Failed to serialize config: {}", e));
+        std::process::exit(1);
+      }
+    }
+    return;
+  }
+
+  println!("{}", "Config file location:".bright_cyan());
+  println!("  {}", UserConfig::get_config_path_display().bright_white());
+}
+
+fn handle_config_check() {
+  let path = UserConfig::get_config_path_display();
+  if !UserConfig::config_path().is_ok_and(|p| p.exists()) {
+    println!(
+      "{} No config file at {}, the defaults apply",
+      symbols::info().bright_cyan(),
+      path
+    );
+    return;
+  }
+
+  let config = match UserConfig::validate_file() {
+    Ok(config) => config,
+    Err(e) => {
+      display_error(&e.to_string());
+      std::process::exit(1);
+    }
+  };
+
+  let checks = config.check();
+  display_config_check(&path, &checks);
+  if checks.iter().any(|check| !check.problems.is_empty()) {
+    std::process::exit(1);
+  }
+}
+
+fn handle_version(config: &UserConfig, full: bool) {
+  println!("wtf {}", env!("CARGO_PKG_VERSION"));
+
+  if !full {
+    return;
+  }
+
+  let ai_status = match (
+    ai::check_api_key(config).is_ok(),
+    &config.ai_fallback_provider,
+  ) {
+    (true, Some(fallback)) => format!("Gemini key set, fallback: {}", fallback),
+    (true, None) => "Gemini key set".to_string(),
+    (false, Some(fallback)) => format!("no Gemini key, fallback: {}", fallback),
+    (false, None) => "not configured".to_string(),
+  };
+
+  println!(
+    "  {} {} {} ({})",
+    "Target:".bright_cyan(),
+    std::env::consts::OS,
+    std::env::consts::ARCH,
+    std::env::consts::FAMILY
+  );
+  println!("  {} {}", "Shell: ".bright_cyan(), hook::detect_shell());
+  println!(
+    "  {} {}",
+    "Config:".bright_cyan(),
+    UserConfig::get_config_path_display()
+  );
+  println!("  {} {}", "AI:    ".bright_cyan(), ai_status);
+  println!(
+    "  {} auto {}, ai {}",
+    "Modes: ".bright_cyan(),
+    if config.auto_mode { "on" } else { "off" },
+    if config.ai_mode { "on" } else { "off" }
+  );
+}
+
+fn default_editor() -> String {
+  if cfg!(target_os = "windows") {
+    "notepad".to_string()
+  } else if path::command_exists("nano") {
+    "nano".to_string()
+  } else {
+    "vi".to_string()
+  }
+}
+
+fn handle_edit(config: &UserConfig) {
+  use std::env;
+  use std::process::Command;
+
+  let config_path = match UserConfig::config_path() {
+    Ok(p) => p,
+    Err(e) => {
+      display_error(&e.to_string());
+      std::process::exit(1);
+    }
+  };
+
+  if !config_path.exists() {
+    if let Err(e) = config.save() {
+      display_error(&format!("Failed to create config: {}", e));
+      std::process::exit(1);
+    }
+  }
+
+  let editor = env::var("EDITOR")
+    .ok()
+    .filter(|e| !e.trim().is_empty())
+    .unwrap_or_else(default_editor);
+
+  let mut editor_parts = editor.split_whitespace();
+  let program = editor_parts.next().unwrap_or("vi");
+
+  let _child = interrupt::ChildGuard::new();
+  let status = Command::new(program)
+    .args(editor_parts)
+    .arg(&config_path)
+    .status();
+
+  match status {
+    Ok(status) if status.success() => {}
+    Ok(status) => {
+      display_error(&format!("Editor exited with status: {}", status));
+      std::process::exit(1);
+    }
+    Err(e) => {
+      display_error(&format!("Failed to launch editor '{}': {}", program, e));
+      std::process::exit(1);
+    }
+  }
+
+  match UserConfig::validate_file() {
+    Ok(_) => println!(
+      "{} {}",
+      symbols::ok().bright_green(),
+      "Config is valid".bright_green()
+    ),
+    Err(e) => {
+      display_error(&e.to_string());
+      println!();
+      println!(
+        "{}",
+        "Your changes were kept. Run 'wtf edit' again to fix the error.".yellow()
+      );
+      std::process::exit(1);
+    }
+  }
+}
+
+fn handle_reset_first_run(config: &mut UserConfig) {
+  config.reset_first_run();
+
+  if let Err(e) = config.save() {
+    display_error(&format!("Failed to save config: {}", e));
+    std::process::exit(1);
+  }
+
+  println!(
+    "{} {}",
+    symbols::ok().bright_green(),
+    "First run reset!".bright_green()
+  );
+  println!();
+  if is_system_installed() {
+    // main() marks the first run done straight away when wtf is on PATH
+    println!(
+      "{}",
+      "wtf is already installed, so the welcome prompt will be skipped. Run 'wtf uninstall' first to see it again."
+        .yellow()
+    );
+  } else {
+    println!(
+      "{}",
+      "The next 'wtf' will show the welcome and install prompt again.".bright_cyan()
+    );
+  }
+}
+
+fn handle_auto_mode(config: &mut UserConfig, enabled: bool) {
+  config.set_auto_mode(enabled);
+
+  if let Err(e) = config.save() {
+    display_error(&format!("Failed to save config: {}", e));
+    std::process::exit(1);
+  }
+
+  if enabled {
     println!(
       "{} {}",
-      "✓".bright_green(),
+      symbols::ok().bright_green(),
       "Auto-mode enabled!".bright_green()
     );
     println!();
@@ -583,7 +2004,7 @@ fn handle_auto_mode(config: &mut UserConfig, enabled: bool) {
   } else {
     println!(
       "{} {}",
-      "✓".bright_green(),
+      symbols::ok().bright_green(),
       "Auto-mode disabled!".bright_green()
     );
     println!();
@@ -605,7 +2026,7 @@ fn handle_toggle_auto(config: &mut UserConfig) {
   if new_state {
     println!(
       "{} {}",
-      "✓".bright_green(),
+      symbols::ok().bright_green(),
       "Auto-mode toggled ON!".bright_green()
     );
     println!();
@@ -616,7 +2037,7 @@ fn handle_toggle_auto(config: &mut UserConfig) {
   } else {
     println!(
       "{} {}",
-      "✓".bright_green(),
+      symbols::ok().bright_green(),
       "Auto-mode toggled OFF!".bright_green()
     );
     println!();
@@ -638,7 +2059,7 @@ fn handle_ai_mode(config: &mut UserConfig, enabled: bool) {
   if enabled {
     println!(
       "{} {}",
-      "✓".bright_green(),
+      symbols::ok().bright_green(),
       "AI mode enabled!".bright_green()
     );
     println!();
@@ -654,7 +2075,7 @@ fn handle_ai_mode(config: &mut UserConfig, enabled: bool) {
   } else {
     println!(
       "{} {}",
-      "✓".bright_green(),
+      symbols::ok().bright_green(),
       "AI mode disabled!".bright_green()
     );
     println!();
@@ -676,7 +2097,7 @@ fn handle_toggle_ai(config: &mut UserConfig) {
   if new_state {
     println!(
       "{} {}",
-      "✓".bright_green(),
+      symbols::ok().bright_green(),
       "AI mode toggled ON!".bright_green()
     );
     println!();
@@ -687,7 +2108,7 @@ fn handle_toggle_ai(config: &mut UserConfig) {
   } else {
     println!(
       "{} {}",
-      "✓".bright_green(),
+      symbols::ok().bright_green(),
       "AI mode toggled OFF!".bright_green()
     );
     println!();
@@ -698,14 +2119,27 @@ fn handle_toggle_ai(config: &mut UserConfig) {
   }
 }
 
-fn handle_save(config: &mut UserConfig, correct: String, debug: bool) {
-  match get_last_command() {
+fn handle_save(
+  config: &mut UserConfig,
+  history_options: &HistoryOptions,
+  correct: String,
+  pick: bool,
+  debug: bool,
+) {
+  let wrong = if pick {
+    pick_recent_command(history_options)
+  } else {
+    get_last_command(history_options)
+  }
+  .map(|raw| sanitize_command(&raw));
+
+  match wrong {
     Ok(last_cmd) => {
       if debug {
         println!("Last command: {}", last_cmd);
       }
 
-      config.add_typo(last_cmd.clone(), correct.clone());
+      config.add_typo(CustomTypo::new(last_cmd.clone(), correct.clone()));
 
       if let Err(e) = config.save() {
         display_error(&format!("Failed to save config: {}", e));
@@ -720,20 +2154,118 @@ fn handle_save(config: &mut UserConfig, correct: String, debug: bool) {
       );
     }
     Err(e) => {
-      display_error(&e);
+      display_error(&e.to_string());
+      std::process::exit(1);
+    }
+  }
+}
+
+const PICK_LIMIT: usize = 10;
+
+const LEARN_LIMIT: usize = 1000;
+
+fn handle_learn(config: &mut UserConfig, history_options: &HistoryOptions, auto_yes: bool) {
+  use std::io::IsTerminal;
+
+  let entries = match get_recent_commands(LEARN_LIMIT, history_options) {
+    Ok(entries) => entries,
+    Err(e) => {
+      display_error(&e.to_string());
       std::process::exit(1);
     }
+  };
+
+  // Newest first, so each command is the retry of the one after it
+  let stats = AcceptanceStats::load();
+  let mut candidates: Vec<(String, String, usize)> = Vec::new();
+  for pair in entries.windows(2) {
+    let Some((wrong, correct)) = retried_typo(&pair[1].command, &pair[0].command) else {
+      continue;
+    };
+    if config.custom_typos.iter().any(|t| t.wrong == wrong) {
+      continue;
+    }
+    // Nothing to learn when wtf already suggests the retry first
+    let known = find_corrections(&pair[1].command, config, &stats)
+      .and_then(|corrections| corrections.into_iter().next())
+      .is_some_and(|best| best.fixed_cmd == pair[0].command);
+    if known {
+      continue;
+    }
+    match candidates
+      .iter_mut()
+      .find(|(w, c, _)| *w == wrong && *c == correct)
+    {
+      Some(candidate) => candidate.2 += 1,
+      None => candidates.push((wrong, correct, 1)),
+    }
+  }
+  candidates.sort_by_key(|(_, _, count)| std::cmp::Reverse(*count));
+
+  display_retried_typos(&candidates, entries.len());
+  if candidates.is_empty() {
+    return;
+  }
+
+  // An empty answer means yes, so a closed stdin must not add everything
+  if !auto_yes && !std::io::stdin().is_terminal() {
+    println!(
+      "{}",
+      "Run 'wtf learn' in a terminal to pick which to save, or add --yes to save all.".dimmed()
+    );
+    return;
+  }
+
+  let mut added = Vec::new();
+  for (wrong, correct, _) in candidates {
+    let question = format!("Save {} {} {}?", wrong, symbols::arrow(), correct);
+    if auto_yes || prompt_yes_no(&question, true) {
+      config.add_typo(CustomTypo::new(wrong.clone(), correct.clone()));
+      added.push((wrong, correct));
+    }
+  }
+
+  if added.is_empty() {
+    return;
+  }
+
+  if let Err(e) = config.save() {
+    display_error(&format!("Failed to save config: {}", e));
+    std::process::exit(1);
+  }
+  for (wrong, correct) in &added {
+    display_added(wrong, correct);
+  }
+}
+
+fn pick_recent_command(history_options: &HistoryOptions) -> Result<String, WtfError> {
+  let mut entries = get_recent_commands(PICK_LIMIT, history_options)?;
+
+  display_recent_commands(&entries, history_options.sources.len() > 1);
+
+  match prompt_choice("Select the wrong command", entries.len()) {
+    Some(idx) => Ok(entries.swap_remove(idx).command),
+    None => {
+      println!("{}", "Cancelled.".yellow());
+      std::process::exit(0);
+    }
   }
 }
 
-fn handle_set_api_key(api_key: String) {
-  match ai::save_api_key(api_key) {
+fn handle_set_api_key(config: &mut UserConfig, api_keys: Vec<String>) {
+  let count = api_keys.len();
+
+  match ai::save_api_keys(config, api_keys) {
     Ok(_) => {
-      println!(
-        "{} {}",
-        "✓".bright_green(),
-        "Google AI API key saved successfully!".bright_green()
-      );
+      let saved = if count == 1 {
+        "Google AI API key saved successfully!".to_string()
+      } else {
+        format!(
+          "{} Google AI API keys saved successfully! They are tried in order when one is rate limited.",
+          count
+        )
+      };
+      println!("{} {}", symbols::ok().bright_green(), saved.bright_green());
       println!();
       println!(
         "{}",
@@ -743,7 +2275,11 @@ fn handle_set_api_key(api_key: String) {
       println!();
       println!(
         "{}",
-        "💡 Tip: The API key is stored in your config directory".dimmed()
+        format!(
+          "{} Tip: The API key is stored in your config directory",
+          symbols::tip()
+        )
+        .dimmed()
       );
     }
     Err(e) => {
@@ -762,7 +2298,7 @@ fn handle_install() {
       println!();
       println!(
         "{} {}",
-        "✓".bright_green(),
+        symbols::ok().bright_green(),
         "Installation complete!".bright_green()
       );
       println!();
@@ -802,16 +2338,40 @@ fn handle_config_history() {
   configure_bash_history();
 }
 
-fn handle_uninstall() {
+async fn handle_self_update(yes: bool) {
+  println!("{}", "Checking for a newer wtf...".bright_cyan());
+  println!();
+
+  if let Err(e) = update::self_update(yes).await {
+    display_error(&e);
+    std::process::exit(1);
+  }
+}
+
+fn handle_uninstall(purge: bool, yes: bool) {
   println!("{}", "Removing WTF from PATH...".bright_cyan());
   println!();
 
-  match path::remove_from_path() {
+  let result = path::remove_from_path();
+
+  // install only ever edits ~/.bashrc outside Windows
+  #[cfg(not(target_os = "windows"))]
+  let result = result.and_then(|_| {
+    if !purge {
+      return Ok(());
+    }
+    println!();
+    revert_bash_history(yes)
+  });
+  #[cfg(target_os = "windows")]
+  let _ = (purge, yes);
+
+  match result {
     Ok(_) => {
       println!();
       println!(
         "{} {}",
-        "✓".bright_green(),
+        symbols::ok().bright_green(),
         "Uninstallation complete!".bright_green()
       );
     }
@@ -822,71 +2382,399 @@ fn handle_uninstall() {
   }
 }
 
-async fn handle_ai_fix(auto_yes: bool, debug: bool) {
-  if let Err(_) = ai::check_api_key() {
+fn handle_purge(config: &UserConfig, export: Option<std::path::PathBuf>, yes: bool) {
+  use std::fs;
+
+  let dir = match config::wtf_dir() {
+    Ok(dir) => dir,
+    Err(e) => {
+      display_error(&e.to_string());
+      std::process::exit(1);
+    }
+  };
+
+  if let Some(export_path) = export {
+    let content = match serde_json::to_string_pretty(&config.custom_typos) {
+      Ok(content) => content,
+      Err(e) => {
+        display_error(&format!("Failed to serialize custom typos: {}", e));
+        std::process::exit(1);
+      }
+    };
+
+    if let Err(e) = fs::write(&export_path, content) {
+      display_error(&format!("Failed to export custom typos: {}", e));
+      std::process::exit(1);
+    }
+
+    println!(
+      "{} Exported {} custom typo(s) to {}",
+      symbols::ok().bright_green(),
+      config.custom_typos.len(),
+      export_path.display()
+    );
+  }
+
+  // A config picked with --config or WTF_CONFIG goes too, but only the file itself:
+  // its directory may well hold things that aren't wtf's
+  let outside_config = UserConfig::config_path()
+    .ok()
+    .filter(|path| !path.starts_with(&dir) && path.is_file());
+
+  if !dir.exists() && outside_config.is_none() {
+    display_info(&format!("{} Nothing to purge.", symbols::info()));
+    return;
+  }
+
+  println!(
+    "{}",
+    format!(
+      "This deletes {} (config, API key, stats and the installed binary).",
+      dir.display()
+    )
+    .yellow()
+  );
+  if let Some(path) = &outside_config {
+    println!(
+      "{}",
+      format!("It also deletes the config file {}.", path.display()).yellow()
+    );
+  }
+
+  if !yes && !prompt_yes_no("Delete everything?", false) {
+    display_cancelled();
+    return;
+  }
+
+  if let Some(path) = &outside_config {
+    if let Err(e) = fs::remove_file(path) {
+      display_error(&format!("Failed to remove {}: {}", path.display(), e));
+      std::process::exit(1);
+    }
+    println!(
+      "{} Removed {}",
+      symbols::ok().bright_green(),
+      path.display()
+    );
+  }
+
+  if !dir.exists() {
+    return;
+  }
+
+  match fs::remove_dir_all(&dir) {
+    Ok(_) => {
+      println!("{} Removed {}", symbols::ok().bright_green(), dir.display());
+      println!(
+        "{}",
+        "Run 'wtf uninstall' first if you also want the PATH entry removed.".dimmed()
+      );
+    }
+    Err(e) => {
+      display_error(&format!("Failed to remove {}: {}", dir.display(), e));
+      std::process::exit(1);
+    }
+  }
+}
+
+fn confirm_and_run(
+  last_cmd: &str,
+  fixed_cmd: &str,
+  options: &FixOptions,
+  user_config: &mut UserConfig,
+) {
+  ensure_exec_allowed(fixed_cmd, user_config);
+
+  let should_run = if let Some(reason) = danger_reason(fixed_cmd).filter(|_| !options.explicit_yes)
+  {
+    confirm_dangerous(fixed_cmd, reason)
+  } else if options.auto_yes {
+    true
+  } else {
+    prompt_yes_no("Run this command?", true)
+  };
+
+  if should_run {
+    run_accepted_fix(last_cmd, fixed_cmd, options, user_config);
+  } else {
+    display_cancelled();
+    if options.eval {
+      std::process::exit(1);
+    }
+  }
+}
+
+// Runs a fix the user said yes to, once they've also agreed to any words it drops.
+fn run_accepted_fix(
+  last_cmd: &str,
+  fixed_cmd: &str,
+  options: &FixOptions,
+  user_config: &mut UserConfig,
+) {
+  let dropped = dropped_words(last_cmd, fixed_cmd);
+  if !dropped.is_empty() && !confirm_dropped_words(&dropped) {
+    display_cancelled();
+    if options.eval {
+      std::process::exit(1);
+    }
+    return;
+  }
+
+  remember_in_directory(last_cmd, fixed_cmd, user_config);
+  if options.eval {
+    emit_fix(fixed_cmd, user_config);
+  } else {
+    run_fix(fixed_cmd, user_config, options.debug);
+  }
+}
+
+// `--ai` with no API key and no fallback provider: a looser offline guess than
+// regular matching, labeled so nobody mistakes it for a model's answer.
+fn handle_heuristic_fix(last_cmd: &str, options: &FixOptions, user_config: &mut UserConfig) {
+  let input = if user_config.normalize_unicode {
+    normalize_unicode(last_cmd)
+  } else {
+    last_cmd.to_string()
+  };
+
+  let Some(fix) = heuristic_fix(&input, user_config, &AcceptanceStats::load()) else {
     ai::display_api_key_help();
     std::process::exit(1);
+  };
+
+  display_corrections(last_cmd, &[]);
+  if !is_quiet() {
+    out!(
+      "{}",
+      format!(
+        "{} No API key set, guessing locally instead (run 'wtf set-api-key' for real AI)",
+        symbols::info()
+      )
+      .yellow()
+    );
+    out!();
   }
+  out!(
+    "{} {} {}",
+    symbols::tip().bright_yellow(),
+    "Heuristic suggestion (not AI):".bright_yellow(),
+    fix.fixed_cmd.bright_white().bold()
+  );
+  decor!();
 
-  match get_last_command() {
+  confirm_and_run(last_cmd, &fix.fixed_cmd, options, user_config);
+}
+
+/// How many times `r` at the AI prompt can ask the model again in one run
+const MAX_AI_REFINEMENTS: usize = 2;
+
+async fn handle_ai_fix(
+  options: &FixOptions,
+  user_config: &mut UserConfig,
+  history_options: &HistoryOptions,
+) {
+  let has_provider =
+    ai::check_api_key(user_config).is_ok() || user_config.ai_fallback_provider.is_some();
+
+  match resolve_command(options, history_options) {
     Ok(last_cmd) => {
-      if debug {
-        println!("Last command: {}", last_cmd);
+      if options.debug {
+        out!("Last command: {}", last_cmd);
+      }
+
+      if !has_provider {
+        if options.no_fallback {
+          ai::display_api_key_help();
+          std::process::exit(1);
+        }
+        handle_heuristic_fix(&last_cmd, options, user_config);
+        return;
       }
 
       display_corrections(&last_cmd, &[]);
 
-      match ai::fix_command_with_ai(&last_cmd).await {
-        Ok(fixed_cmd) => {
-          println!();
-          println!(
-            "{} {} {}",
-            "🤖".bright_cyan(),
-            "AI suggestion:".bright_green(),
-            fixed_cmd.bright_white().bold()
-          );
-          println!();
-
-          let should_run = if auto_yes {
-            true
-          } else {
-            print!("{} [Y/n]: ", "Run this command?".bright_cyan());
-            use std::io::{self, Write};
-            io::stdout().flush().unwrap();
-
-            let mut input = String::new();
-            io::stdin().read_line(&mut input).ok();
-            let answer = input.trim().to_lowercase();
-            answer.is_empty() || answer == "y" || answer == "yes"
-          };
+      let ai_input = if user_config.normalize_unicode {
+        normalize_unicode(&last_cmd)
+      } else {
+        last_cmd.clone()
+      };
+
+      let mut refinements = Vec::new();
+      loop {
+        let result =
+          ai::fix_command_with_ai(&ai_input, user_config, options.explain_ai, &refinements).await;
+
+        let ai_fix = match result {
+          Ok(ai_fix) => ai_fix,
+          Err(e) if !refinements.is_empty() => {
+            display_error(&format!("AI fix failed: {}", e));
+            std::process::exit(1);
+          }
+          Err(e) => {
+            fall_back_from_ai(&e, options, user_config, history_options);
+            return;
+          }
+        };
+
+        if options.debug {
+          if let Some(index) = ai_fix.key_index {
+            out!("Used API key #{}", index + 1);
+          }
+        }
+
+        if !ai_fix.alternatives.is_empty() {
+          pick_ai_candidate(&last_cmd, ai_fix, options, user_config);
+          return;
+        }
+
+        let fixed_cmd = ai_fix.command;
+        decor!();
+        let label = if refinements.is_empty() {
+          "AI suggestion"
+        } else {
+          "Refined AI suggestion"
+        };
+        out!(
+          "{} {} {}",
+          symbols::ai().bright_cyan(),
+          format!("{} ({}):", label, ai_fix.provider.display_name()).bright_green(),
+          fixed_cmd.bright_white().bold()
+        );
+        if let Some(rationale) = &ai_fix.rationale {
+          out!("   {}", rationale.dimmed());
+        }
+        decor!();
+
+        ensure_exec_allowed(&fixed_cmd, user_config);
+
+        // Dangerous fixes get the usual warning instead, and the rounds are capped
+        // since each one is another request
+        let can_refine = !options.auto_yes
+          && refinements.len() < MAX_AI_REFINEMENTS
+          && danger_reason(&fixed_cmd).is_none();
+        if !can_refine {
+          confirm_and_run(&last_cmd, &fixed_cmd, options, user_config);
+          return;
+        }
 
-          if should_run {
-            display_success(&fixed_cmd);
-            if let Err(e) = execute_command(&fixed_cmd) {
-              display_error(&e);
+        match prompt_run_or_refine() {
+          RunAnswer::Run => {
+            run_accepted_fix(&last_cmd, &fixed_cmd, options, user_config);
+            return;
+          }
+          RunAnswer::Refine(hint) => refinements.push(ai::Refinement {
+            suggestion: fixed_cmd,
+            hint,
+          }),
+          RunAnswer::Cancel => {
+            display_cancelled();
+            if options.eval {
               std::process::exit(1);
             }
-          } else {
-            println!("{}", "Cancelled.".yellow());
+            return;
           }
         }
-        Err(e) => {
-          display_error(&format!("AI fix failed: {}", e));
-          println!();
-          println!(
-            "{}",
-            "💡 Tip: Falling back to built-in typo detection...".yellow()
-          );
-          println!();
-
-          let user_config = UserConfig::load();
-          handle_fix(auto_yes, debug, &user_config);
-        }
       }
     }
     Err(e) => {
-      display_error(&e);
+      display_error(&e.to_string());
+      std::process::exit(1);
+    }
+  }
+}
+
+// `ai_candidate_count` above 1: the distinct candidates as a list, like pattern matches.
+fn pick_ai_candidate(
+  last_cmd: &str,
+  ai_fix: ai::AiFix,
+  options: &FixOptions,
+  user_config: &mut UserConfig,
+) {
+  let provider = ai_fix.provider.display_name();
+  let mut candidates = vec![(ai_fix.command, ai_fix.rationale)];
+  candidates.extend(
+    ai_fix
+      .alternatives
+      .into_iter()
+      .map(|alternative| (alternative.command, alternative.rationale)),
+  );
+
+  decor!();
+  out!(
+    "{} {}",
+    symbols::ai().bright_cyan(),
+    format!("AI suggestions ({}):", provider).bright_green()
+  );
+  let suggestions: Vec<(&str, &str)> = candidates
+    .iter()
+    .map(|(command, rationale)| (command.as_str(), rationale.as_deref().unwrap_or(provider)))
+    .collect();
+  display_suggestions(last_cmd, &suggestions);
+
+  let selected = if let Some(n) = options.run_index {
+    if n == 0 || n > candidates.len() {
+      display_error(&format!(
+        "--run {} is out of range: there are {} suggestion(s)",
+        n,
+        candidates.len()
+      ));
       std::process::exit(1);
     }
+    n - 1
+  } else if options.auto_yes {
+    0
+  } else {
+    match prompt_selection(candidates.len()) {
+      Some(index) => index,
+      None => {
+        display_cancelled();
+        if options.eval {
+          std::process::exit(1);
+        }
+        return;
+      }
+    }
+  };
+
+  let fixed_cmd = &candidates[selected].0;
+  ensure_exec_allowed(fixed_cmd, user_config);
+  if !options.explicit_yes {
+    if let Some(reason) = danger_reason(fixed_cmd) {
+      if !confirm_dangerous(fixed_cmd, reason) {
+        display_cancelled();
+        if options.eval {
+          std::process::exit(1);
+        }
+        return;
+      }
+    }
+  }
+
+  run_accepted_fix(last_cmd, fixed_cmd, options, user_config);
+}
+
+fn fall_back_from_ai(
+  error: &WtfError,
+  options: &FixOptions,
+  user_config: &mut UserConfig,
+  history_options: &HistoryOptions,
+) {
+  display_error(&format!("AI fix failed: {}", error));
+  if options.no_fallback {
+    std::process::exit(1);
   }
+  if !is_quiet() {
+    out!();
+    out!(
+      "{}",
+      format!(
+        "{} Tip: Falling back to built-in typo detection...",
+        symbols::tip()
+      )
+      .yellow()
+    );
+    out!();
+  }
+
+  handle_fix(options, user_config, history_options);
 }
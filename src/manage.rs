@@ -0,0 +1,298 @@
+// `wtf manage`: a small full-screen editor for the custom typo list.
+
+use crate::config::CustomTypo;
+use crate::interrupt;
+use crate::symbols;
+use colored::*;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::Print;
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use std::io::{self, Write};
+
+const HELP: &str =
+  "a add  e/Enter edit  d delete  K/J move up/down  q save & quit  Esc/Ctrl-C discard";
+
+// Rows taken by the title, blank lines, prompt, and help footer.
+const CHROME_ROWS: u16 = 6;
+
+pub enum Outcome {
+  Save(Vec<CustomTypo>),
+  Discard,
+}
+
+enum Field {
+  Wrong,
+  Correct,
+}
+
+struct Input {
+  field: Field,
+  wrong: String,
+  correct: String,
+  // Index being edited, None when adding
+  editing: Option<usize>,
+}
+
+impl Input {
+  fn buffer(&mut self) -> &mut String {
+    match self.field {
+      Field::Wrong => &mut self.wrong,
+      Field::Correct => &mut self.correct,
+    }
+  }
+}
+
+struct State {
+  typos: Vec<CustomTypo>,
+  selected: usize,
+  offset: usize,
+  input: Option<Input>,
+  message: Option<String>,
+}
+
+// Puts the terminal back even if drawing fails halfway.
+struct TerminalGuard;
+
+impl TerminalGuard {
+  fn enter() -> io::Result<Self> {
+    terminal::enable_raw_mode()?;
+    if let Err(e) = execute!(io::stdout(), EnterAlternateScreen, Hide) {
+      let _ = terminal::disable_raw_mode();
+      return Err(e);
+    }
+    Ok(TerminalGuard)
+  }
+}
+
+impl Drop for TerminalGuard {
+  fn drop(&mut self) {
+    let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+    let _ = terminal::disable_raw_mode();
+  }
+}
+
+pub fn run(typos: Vec<CustomTypo>) -> Result<Outcome, String> {
+  let guard = TerminalGuard::enter().map_err(|e| format!("Failed to start terminal UI: {}", e))?;
+
+  let mut state = State {
+    typos,
+    selected: 0,
+    offset: 0,
+    input: None,
+    message: None,
+  };
+
+  loop {
+    draw(&mut state).map_err(|e| format!("Failed to draw terminal UI: {}", e))?;
+
+    let key = match event::read().map_err(|e| format!("Failed to read key: {}", e))? {
+      // Windows also reports key releases
+      Event::Key(key) if key.kind == KeyEventKind::Press => key,
+      _ => continue,
+    };
+
+    if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+      // Leave the alternate screen first, exiting skips the guard's Drop
+      drop(guard);
+      interrupt::exit_interrupted();
+    }
+
+    if state.input.is_some() {
+      handle_input_key(&mut state, key);
+      continue;
+    }
+
+    state.message = None;
+
+    match key.code {
+      KeyCode::Char('q') => return Ok(Outcome::Save(state.typos)),
+      KeyCode::Esc => return Ok(Outcome::Discard),
+      KeyCode::Up | KeyCode::Char('k') => state.selected = state.selected.saturating_sub(1),
+      KeyCode::Down | KeyCode::Char('j') if state.selected + 1 < state.typos.len() => {
+        state.selected += 1;
+      }
+      KeyCode::Char('K') => move_selected(&mut state, -1),
+      KeyCode::Char('J') => move_selected(&mut state, 1),
+      KeyCode::Char('a') => {
+        state.input = Some(Input {
+          field: Field::Wrong,
+          wrong: String::new(),
+          correct: String::new(),
+          editing: None,
+        });
+      }
+      KeyCode::Char('e') | KeyCode::Enter => {
+        if let Some(typo) = state.typos.get(state.selected) {
+          state.input = Some(Input {
+            field: Field::Wrong,
+            wrong: typo.wrong.clone(),
+            correct: typo.correct.clone(),
+            editing: Some(state.selected),
+          });
+        }
+      }
+      KeyCode::Char('d') | KeyCode::Delete if state.selected < state.typos.len() => {
+        let removed = state.typos.remove(state.selected);
+        state.message = Some(format!("Deleted {}", removed.wrong));
+        if state.selected >= state.typos.len() {
+          state.selected = state.typos.len().saturating_sub(1);
+        }
+      }
+      _ => {}
+    }
+  }
+}
+
+fn move_selected(state: &mut State, delta: isize) {
+  let Some(target) = state.selected.checked_add_signed(delta) else {
+    return;
+  };
+  if target < state.typos.len() {
+    state.typos.swap(state.selected, target);
+    state.selected = target;
+  }
+}
+
+fn handle_input_key(state: &mut State, key: KeyEvent) {
+  let Some(input) = state.input.as_mut() else {
+    return;
+  };
+
+  match key.code {
+    KeyCode::Esc => state.input = None,
+    KeyCode::Backspace => {
+      input.buffer().pop();
+    }
+    KeyCode::Char(c) => input.buffer().push(c),
+    KeyCode::Enter => match input.field {
+      Field::Wrong if !input.wrong.trim().is_empty() => input.field = Field::Correct,
+      Field::Correct if !input.correct.trim().is_empty() => {
+        if let Some(input) = state.input.take() {
+          commit_input(state, input);
+        }
+      }
+      _ => {}
+    },
+    _ => {}
+  }
+}
+
+fn commit_input(state: &mut State, input: Input) {
+  let wrong = input.wrong.trim().to_string();
+  let correct = input.correct.trim().to_string();
+
+  let duplicate = state
+    .typos
+    .iter()
+    .enumerate()
+    .any(|(i, typo)| typo.wrong == wrong && Some(i) != input.editing);
+  if duplicate {
+    state.message = Some(format!("'{}' already has a fix", wrong));
+    return;
+  }
+
+  match input.editing {
+    Some(index) => {
+      // Keep the match mode and category of the entry being edited
+      let typo = &mut state.typos[index];
+      typo.wrong = wrong;
+      typo.correct = correct;
+    }
+    None => {
+      state.typos.push(CustomTypo::new(wrong, correct));
+      state.selected = state.typos.len() - 1;
+    }
+  }
+}
+
+fn draw(state: &mut State) -> io::Result<()> {
+  let (width, height) = terminal::size()?;
+  let visible = height.saturating_sub(CHROME_ROWS).max(1) as usize;
+
+  if state.selected < state.offset {
+    state.offset = state.selected;
+  } else if state.selected >= state.offset + visible {
+    state.offset = state.selected + 1 - visible;
+  }
+
+  let mut stdout = io::stdout();
+  queue!(stdout, Clear(ClearType::All), MoveTo(0, 0))?;
+  queue!(
+    stdout,
+    Print(format!(
+      "{} {}",
+      "Custom Typos".bright_cyan().bold(),
+      format!("({})", state.typos.len()).dimmed()
+    ))
+  )?;
+
+  if state.typos.is_empty() {
+    queue!(
+      stdout,
+      MoveTo(0, 2),
+      Print("No custom typos yet. Press 'a' to add one.".yellow())
+    )?;
+  }
+
+  for (row, (i, typo)) in state
+    .typos
+    .iter()
+    .enumerate()
+    .skip(state.offset)
+    .take(visible)
+    .enumerate()
+  {
+    let line = truncate(
+      &format!("{} {} {}", typo.wrong, symbols::arrow(), typo.correct),
+      width.saturating_sub(4) as usize,
+    );
+    let line = if i == state.selected {
+      format!("> {}", line).bright_white().bold()
+    } else {
+      format!("  {}", line).normal()
+    };
+    queue!(stdout, MoveTo(0, 2 + row as u16), Print(line))?;
+  }
+
+  let prompt_row = height.saturating_sub(3);
+  if let Some(input) = &state.input {
+    let (label, value) = match input.field {
+      Field::Wrong => ("Wrong:", &input.wrong),
+      Field::Correct => ("Correct:", &input.correct),
+    };
+    queue!(
+      stdout,
+      MoveTo(0, prompt_row),
+      Print(format!("{} {}_", label.bright_cyan(), value))
+    )?;
+  } else if let Some(message) = &state.message {
+    queue!(stdout, MoveTo(0, prompt_row), Print(message.yellow()))?;
+  }
+
+  let help = if state.input.is_some() {
+    "Enter confirm  Esc cancel"
+  } else {
+    HELP
+  };
+  queue!(
+    stdout,
+    MoveTo(0, height.saturating_sub(1)),
+    Print(truncate(help, width as usize).dimmed())
+  )?;
+
+  stdout.flush()
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+  if text.chars().count() <= max_chars {
+    return text.to_string();
+  }
+  let ellipsis = symbols::ellipsis();
+  let kept: String = text
+    .chars()
+    .take(max_chars.saturating_sub(ellipsis.chars().count()))
+    .collect();
+  format!("{}{}", kept, ellipsis)
+}
@@ -0,0 +1,213 @@
+// Just enough shell syntax awareness to avoid mangling commands while fixing them.
+
+/// A `$(...)` or backtick command substitution. `start..end` is the byte range of
+/// the command inside it, without the `$(`/`)` or backticks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Substitution {
+  pub start: usize,
+  pub end: usize,
+}
+
+/// Finds the outermost command substitutions, skipping anything in single quotes.
+/// Nested substitutions are left inside their parent. Returns None when one is left open.
+pub fn find_substitutions(cmd: &str) -> Option<Vec<Substitution>> {
+  let bytes = cmd.as_bytes();
+  let mut subs = Vec::new();
+  let mut in_single = false;
+  let mut in_double = false;
+  let mut i = 0;
+
+  while i < bytes.len() {
+    let c = bytes[i];
+
+    if in_single {
+      if c == b'\'' {
+        in_single = false;
+      }
+      i += 1;
+      continue;
+    }
+
+    match c {
+      b'\\' => i += 1,
+      b'\'' if !in_double => in_single = true,
+      b'"' => in_double = !in_double,
+      b'$' if bytes.get(i + 1) == Some(&b'(') => {
+        let start = i + 2;
+        let end = matching_paren(bytes, start)?;
+        // `$((1 + 2))` is arithmetic, not a command
+        if bytes.get(start) != Some(&b'(') {
+          subs.push(Substitution { start, end });
+        }
+        i = end;
+      }
+      b'`' => {
+        let start = i + 1;
+        let end = closing_backtick(bytes, start)?;
+        subs.push(Substitution { start, end });
+        i = end;
+      }
+      _ => {}
+    }
+
+    i += 1;
+  }
+
+  Some(subs)
+}
+
+/// One command of a `&&`/`||`/`;` chain. `start..end` is its byte range, without
+/// the separators but with any surrounding spaces.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+  pub start: usize,
+  pub end: usize,
+}
+
+/// Splits a command on `&&`, `||` and `;`, leaving anything in quotes or command
+/// substitutions alone. Returns None when there is nothing to split or quoting is left open.
+pub fn split_chain(cmd: &str) -> Option<Vec<Segment>> {
+  let bytes = cmd.as_bytes();
+  let mut segments = Vec::new();
+  let mut in_single = false;
+  let mut in_double = false;
+  let mut start = 0;
+  let mut i = 0;
+
+  while i < bytes.len() {
+    let c = bytes[i];
+
+    if in_single {
+      if c == b'\'' {
+        in_single = false;
+      }
+      i += 1;
+      continue;
+    }
+
+    match c {
+      b'\\' => i += 1,
+      b'\'' if !in_double => in_single = true,
+      b'"' => in_double = !in_double,
+      b'$' if bytes.get(i + 1) == Some(&b'(') => i = matching_paren(bytes, i + 2)?,
+      b'`' => i = closing_backtick(bytes, i + 1)?,
+      b'&' | b'|' if !in_double && bytes.get(i + 1) == Some(&c) => {
+        segments.push(Segment { start, end: i });
+        i += 1;
+        start = i + 1;
+      }
+      b';' if !in_double => {
+        segments.push(Segment { start, end: i });
+        start = i + 1;
+      }
+      _ => {}
+    }
+
+    i += 1;
+  }
+
+  if in_single || in_double || segments.is_empty() {
+    return None;
+  }
+
+  segments.push(Segment {
+    start,
+    end: bytes.len(),
+  });
+  Some(segments)
+}
+
+/// True when every `$(` and backtick in the command is closed.
+pub fn substitutions_balanced(cmd: &str) -> bool {
+  find_substitutions(cmd).is_some()
+}
+
+// Index of the `)` closing a `$(` whose contents start at `start`.
+fn matching_paren(bytes: &[u8], start: usize) -> Option<usize> {
+  let mut depth = 1;
+  let mut in_single = false;
+  let mut in_double = false;
+  let mut i = start;
+
+  while i < bytes.len() {
+    let c = bytes[i];
+
+    if in_single {
+      if c == b'\'' {
+        in_single = false;
+      }
+      i += 1;
+      continue;
+    }
+
+    match c {
+      b'\\' => i += 1,
+      b'\'' if !in_double => in_single = true,
+      b'"' => in_double = !in_double,
+      b'`' if !in_double => i = closing_backtick(bytes, i + 1)?,
+      b'(' if !in_double => depth += 1,
+      b')' if !in_double => {
+        depth -= 1;
+        if depth == 0 {
+          return Some(i);
+        }
+      }
+      // `$(` inside double quotes still opens a nested substitution
+      b'$' if in_double && bytes.get(i + 1) == Some(&b'(') => {
+        i = matching_paren(bytes, i + 2)?;
+      }
+      _ => {}
+    }
+
+    i += 1;
+  }
+
+  None
+}
+
+fn closing_backtick(bytes: &[u8], start: usize) -> Option<usize> {
+  let mut i = start;
+
+  while i < bytes.len() {
+    match bytes[i] {
+      b'\\' => i += 1,
+      b'`' => return Some(i),
+      _ => {}
+    }
+    i += 1;
+  }
+
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn substitutions(cmd: &str) -> Option<Vec<&str>> {
+    find_substitutions(cmd).map(|subs| subs.iter().map(|sub| &cmd[sub.start..sub.end]).collect())
+  }
+
+  #[test]
+  fn find_substitutions_keeps_nested_ones_inside_their_parent() {
+    assert_eq!(
+      substitutions("echo $(basename $(pwd))"),
+      Some(vec!["basename $(pwd)"])
+    );
+    assert_eq!(
+      substitutions("echo $(a) `b` \"$(c $(d))\""),
+      Some(vec!["a", "b", "c $(d)"])
+    );
+  }
+
+  #[test]
+  fn find_substitutions_skips_single_quotes_and_arithmetic() {
+    assert_eq!(substitutions("echo '$(pwd)' $((1 + 2))"), Some(vec![]));
+  }
+
+  #[test]
+  fn find_substitutions_rejects_unbalanced_input() {
+    assert_eq!(substitutions("echo $(basename $(pwd)"), None);
+    assert_eq!(substitutions("echo `pwd"), None);
+  }
+}
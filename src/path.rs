@@ -1,21 +1,166 @@
+use crate::config::wtf_dir;
+use crate::symbols;
 use colored::Colorize;
+use std::cmp::Ordering;
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
 pub fn get_install_dir() -> Result<PathBuf, String> {
-  let home = dirs::home_dir().ok_or("Could not find home directory")?;
-  Ok(home.join(".wtf").join("bin"))
+  Ok(wtf_dir().map_err(|e| e.to_string())?.join("bin"))
+}
+
+pub fn command_exists(cmd: &str) -> bool {
+  if cmd.contains('/') || cmd.contains('\\') {
+    return PathBuf::from(cmd).is_file();
+  }
+
+  let path_var = match env::var_os("PATH") {
+    Some(p) => p,
+    None => return false,
+  };
+
+  let extensions: Vec<String> = if cfg!(target_os = "windows") {
+    env::var("PATHEXT")
+      .unwrap_or_else(|_| ".EXE;.CMD;.BAT;.COM".to_string())
+      .split(';')
+      .map(|ext| ext.to_string())
+      .chain(std::iter::once(String::new()))
+      .collect()
+  } else {
+    vec![String::new()]
+  };
+
+  env::split_paths(&path_var).any(|dir| {
+    extensions
+      .iter()
+      .any(|ext| dir.join(format!("{}{}", cmd, ext)).is_file())
+  })
 }
 
 pub fn get_current_exe() -> Result<PathBuf, String> {
   env::current_exe().map_err(|e| format!("Could not get current executable path: {}", e))
 }
 
+// Says whether this replaces an existing install, then copies the running binary over it.
+fn install_binary(current_exe: &Path, dest: &Path) -> Result<(), String> {
+  let same_file = match (fs::canonicalize(current_exe), fs::canonicalize(dest)) {
+    (Ok(a), Ok(b)) => a == b,
+    _ => false,
+  };
+  if same_file {
+    println!(
+      "{}",
+      format!(
+        "{} Already running the installed binary, nothing to copy.",
+        symbols::info()
+      )
+      .bright_blue()
+    );
+    return Ok(());
+  }
+
+  let new_version = env!("CARGO_PKG_VERSION");
+
+  if !dest.exists() {
+    println!(
+      "{} Fresh install of wtf {}",
+      symbols::ok().bright_green(),
+      new_version
+    );
+  } else {
+    match installed_version(dest) {
+      Some(old_version) => match compare_versions(&old_version, new_version) {
+        Ordering::Less => println!(
+          "{} Upgrading wtf {} {} {}",
+          symbols::ok().bright_green(),
+          old_version,
+          symbols::arrow(),
+          new_version
+        ),
+        Ordering::Greater => println!(
+          "{}",
+          format!(
+            "{} Downgrading wtf {} {} {}",
+            symbols::warn(),
+            old_version,
+            symbols::arrow(),
+            new_version
+          )
+          .yellow()
+        ),
+        Ordering::Equal => println!(
+          "{} Reinstalling wtf {}",
+          symbols::ok().bright_green(),
+          new_version
+        ),
+      },
+      None => println!(
+        "{}",
+        format!(
+          "{} Replacing an existing binary of unknown version with wtf {}",
+          symbols::warn(),
+          new_version
+        )
+        .yellow()
+      ),
+    }
+  }
+
+  copy_binary(current_exe, dest).map_err(|e| copy_error(dest, e))
+}
+
 #[cfg(target_os = "windows")]
-pub fn add_to_path() -> Result<(), String> {
-  use std::process::Command;
+fn copy_binary(current_exe: &Path, dest: &Path) -> std::io::Result<()> {
+  fs::copy(current_exe, dest).map(|_| ())
+}
+
+// Copying straight over a binary that is running fails with "Text file busy",
+// so copy next to it and rename into place.
+#[cfg(not(target_os = "windows"))]
+fn copy_binary(current_exe: &Path, dest: &Path) -> std::io::Result<()> {
+  let staged = dest.with_extension("new");
+  fs::copy(current_exe, &staged)?;
+  fs::rename(&staged, dest)
+}
 
+pub fn installed_version(binary: &Path) -> Option<String> {
+  let output = Command::new(binary).arg("--version").output().ok()?;
+  if !output.status.success() {
+    return None;
+  }
+
+  // clap prints "wtf 0.0.4"
+  String::from_utf8_lossy(&output.stdout)
+    .split_whitespace()
+    .nth(1)
+    .map(|v| v.to_string())
+}
+
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+  let parse = |v: &str| -> Vec<u64> {
+    v.split(['.', '-'])
+      .map_while(|part| part.parse().ok())
+      .collect()
+  };
+  parse(a).cmp(&parse(b))
+}
+
+fn copy_error(dest: &Path, e: std::io::Error) -> String {
+  // ERROR_SHARING_VIOLATION / ERROR_LOCK_VIOLATION: the old wtf.exe is still running somewhere.
+  if cfg!(target_os = "windows") && matches!(e.raw_os_error(), Some(32) | Some(33)) {
+    return format!(
+      "{} is in use by another program. Close any running wtf (or terminals using it) and try again.",
+      dest.display()
+    );
+  }
+
+  format!("Failed to copy binary: {}", e)
+}
+
+#[cfg(target_os = "windows")]
+pub fn add_to_path() -> Result<(), String> {
   let install_dir = get_install_dir()?;
   let current_exe = get_current_exe()?;
 
@@ -23,9 +168,9 @@ pub fn add_to_path() -> Result<(), String> {
     .map_err(|e| format!("Failed to create install directory: {}", e))?;
 
   let dest = install_dir.join("wtf.exe");
-  fs::copy(&current_exe, &dest).map_err(|e| format!("Failed to copy binary: {}", e))?;
+  install_binary(&current_exe, &dest)?;
 
-  println!("{} Binary copied to:", "✓".bright_green());
+  println!("{} Binary copied to:", symbols::ok().bright_green());
   println!("  {}", dest.display().to_string().bright_white());
   println!();
 
@@ -49,12 +194,16 @@ pub fn add_to_path() -> Result<(), String> {
     .map_err(|e| format!("Failed to update PATH: {}", e))?;
 
   if output.status.success() {
-    println!("{} Added to PATH:", "✓".bright_green());
+    println!("{} Added to PATH:", symbols::ok().bright_green());
     println!("  {}", install_dir_str.bright_white());
     println!();
     println!(
       "{}",
-      "⚠️  Restart your terminal for PATH changes to take effect".bright_yellow()
+      format!(
+        "{} Restart your terminal for PATH changes to take effect",
+        symbols::warn()
+      )
+      .bright_yellow()
     );
   } else {
     return Err("Failed to add to PATH. You may need administrator privileges.".to_string());
@@ -72,7 +221,7 @@ pub fn add_to_path() -> Result<(), String> {
     .map_err(|e| format!("Failed to create install directory: {}", e))?;
 
   let dest = install_dir.join("wtf");
-  fs::copy(&current_exe, &dest).map_err(|e| format!("Failed to copy binary: {}", e))?;
+  install_binary(&current_exe, &dest)?;
 
   #[cfg(unix)]
   {
@@ -84,14 +233,14 @@ pub fn add_to_path() -> Result<(), String> {
     fs::set_permissions(&dest, perms).map_err(|e| format!("Failed to set permissions: {}", e))?;
   }
 
-  println!("{} Binary installed to:", "✓".bright_green());
+  println!("{} Binary installed to:", symbols::ok().bright_green());
   println!("  {}", dest.display().to_string().bright_white());
   println!();
 
   let install_dir_str = install_dir.to_string_lossy().to_string();
 
   let shell = env::var("SHELL").unwrap_or_else(|_| String::from("unknown"));
-  let shell_name = shell.split('/').last().unwrap_or("sh");
+  let shell_name = shell.split('/').next_back().unwrap_or("sh");
 
   println!("{}", "Add to your shell configuration:".bright_cyan());
   println!();
@@ -122,8 +271,6 @@ pub fn add_to_path() -> Result<(), String> {
 
 #[cfg(target_os = "windows")]
 pub fn remove_from_path() -> Result<(), String> {
-  use std::process::Command;
-
   let install_dir = get_install_dir()?;
   let install_dir_str = install_dir.to_string_lossy().to_string();
 
@@ -142,16 +289,19 @@ pub fn remove_from_path() -> Result<(), String> {
     .map_err(|e| format!("Failed to update PATH: {}", e))?;
 
   if output.status.success() {
-    println!("{} Removed from PATH:", "✓".bright_green());
+    println!("{} Removed from PATH:", symbols::ok().bright_green());
     println!("  {}", install_dir_str.bright_white());
     println!();
 
     let binary = install_dir.join("wtf.exe");
     if binary.exists() {
       if let Err(e) = fs::remove_file(&binary) {
-        println!("{}", format!("⚠️  Could not remove binary: {}", e).yellow());
+        println!(
+          "{}",
+          format!("{} Could not remove binary: {}", symbols::warn(), e).yellow()
+        );
       } else {
-        println!("{} Binary removed", "✓".bright_green());
+        println!("{} Binary removed", symbols::ok().bright_green());
       }
     }
 
@@ -166,7 +316,11 @@ pub fn remove_from_path() -> Result<(), String> {
     println!();
     println!(
       "{}",
-      "⚠️  Restart your terminal for PATH changes to take effect".bright_yellow()
+      format!(
+        "{} Restart your terminal for PATH changes to take effect",
+        symbols::warn()
+      )
+      .bright_yellow()
     );
   } else {
     return Err("Failed to remove from PATH".to_string());
@@ -184,19 +338,19 @@ pub fn remove_from_path() -> Result<(), String> {
   println!();
 
   let shell = env::var("SHELL").unwrap_or_else(|_| String::from("unknown"));
-  let shell_name = shell.split('/').last().unwrap_or("sh");
+  let shell_name = shell.split('/').next_back().unwrap_or("sh");
 
   println!("{}", "Remove from your shell configuration:".bright_cyan());
   println!();
 
   match shell_name {
     "bash" => println!(
-      "  Edit ~/.bashrc and remove the line with: {}/.wtf/bin",
-      dirs::home_dir().unwrap().display()
+      "  Edit ~/.bashrc and remove the line with: {}",
+      install_dir_str
     ),
     "zsh" => println!(
-      "  Edit ~/.zshrc and remove the line with: {}/.wtf/bin",
-      dirs::home_dir().unwrap().display()
+      "  Edit ~/.zshrc and remove the line with: {}",
+      install_dir_str
     ),
     "fish" => {
       println!("  Run: set -U fish_user_paths (string match -v ~/.wtf/bin $fish_user_paths)")
@@ -209,7 +363,7 @@ pub fn remove_from_path() -> Result<(), String> {
   let binary = install_dir.join("wtf");
   if binary.exists() {
     fs::remove_file(&binary).map_err(|e| format!("Failed to remove binary: {}", e))?;
-    println!("{} Binary removed from:", "✓".bright_green());
+    println!("{} Binary removed from:", symbols::ok().bright_green());
     println!("  {}", binary.display().to_string().bright_white());
   }
 
@@ -217,7 +371,7 @@ pub fn remove_from_path() -> Result<(), String> {
     if let Ok(entries) = fs::read_dir(&install_dir) {
       if entries.count() == 0 {
         fs::remove_dir(&install_dir).map_err(|e| format!("Failed to remove directory: {}", e))?;
-        println!("{} Directory removed", "✓".bright_green());
+        println!("{} Directory removed", symbols::ok().bright_green());
       }
     }
   }
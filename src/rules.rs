@@ -0,0 +1,1282 @@
+use crate::commands::{get_flags, get_package_manager_verbs, get_subcommands};
+use crate::config::UserConfig;
+use crate::corrections::{Correction, CorrectionSource};
+use crate::path::command_exists;
+use crate::symbols;
+use regex::Regex;
+use std::env;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use strsim::jaro_winkler;
+
+type Rule = fn(&str) -> Vec<Correction>;
+
+fn get_rules() -> Vec<Rule> {
+  vec![
+    swap_transposed_words,
+    missing_executable_bit,
+    fix_flag_dashes,
+    python3_fallback,
+    docker_compose_form,
+    rm_to_trash,
+    git_branch_typo,
+    redirect_into_command,
+    redirect_to_fd_number,
+    package_manager_verb,
+    dev_tool_subcommand,
+    quote_flag_argument,
+    grep_pattern,
+    misplaced_flags,
+    env_var_typo,
+  ]
+}
+
+// Rules that list directories, only run with `fuzzy_paths` enabled.
+fn get_filesystem_rules() -> Vec<Rule> {
+  vec![file_argument_typo]
+}
+
+pub fn apply_rules(cmd: &str, config: &UserConfig) -> Vec<Correction> {
+  let mut rules = get_rules();
+  if config.fuzzy_paths {
+    rules.extend(get_filesystem_rules());
+  }
+
+  rules.iter().flat_map(|rule| rule(cmd)).collect()
+}
+
+// `commit git -m "msg"` -> `git commit -m "msg"`
+fn swap_transposed_words(cmd: &str) -> Vec<Correction> {
+  let parts: Vec<&str> = cmd.split_whitespace().collect();
+  if parts.len() < 2 {
+    return Vec::new();
+  }
+
+  let subcommands = get_subcommands();
+
+  // Only swap when the first word is not itself a known primary command,
+  // so legitimate invocations are never reordered.
+  if subcommands.iter().any(|(primary, _)| *primary == parts[0]) {
+    return Vec::new();
+  }
+
+  let is_transposed = subcommands
+    .iter()
+    .any(|(primary, verbs)| *primary == parts[1] && verbs.contains(&parts[0]));
+
+  if !is_transposed {
+    return Vec::new();
+  }
+
+  // `status` or `build` can be a real program (a script on PATH), so only swap
+  // when the first word isn't runnable and the second one is.
+  if command_exists(parts[0]) || !command_exists(parts[1]) {
+    return Vec::new();
+  }
+
+  let mut reordered = vec![parts[1], parts[0]];
+  reordered.extend_from_slice(&parts[2..]);
+
+  vec![Correction {
+    fixed_cmd: reordered.join(" "),
+    reason: format!("swapped '{}' and '{}'", parts[0], parts[1]),
+    confidence: 0.9,
+    source: CorrectionSource::Rule,
+  }]
+}
+
+// `git commit --m "msg"` -> `git commit -m "msg"`, `ls -color` -> `ls --color`
+fn fix_flag_dashes(cmd: &str) -> Vec<Correction> {
+  let parts: Vec<&str> = cmd.split_whitespace().collect();
+  let Some(&program) = parts.first() else {
+    return Vec::new();
+  };
+
+  let Some((_, flags)) = get_flags().into_iter().find(|(name, _)| *name == program) else {
+    return Vec::new();
+  };
+
+  let mut fixed_parts = Vec::with_capacity(parts.len());
+  let mut changed = Vec::new();
+  let mut positional = false;
+
+  for part in parts {
+    // Everything after `--` is positional, never a flag.
+    if part == "--" {
+      positional = true;
+    }
+
+    match redash_flag(part, &flags).filter(|_| !positional) {
+      Some(fixed) => {
+        changed.push(format!("'{}' {} '{}'", part, symbols::arrow(), fixed));
+        fixed_parts.push(fixed);
+      }
+      None => fixed_parts.push(part.to_string()),
+    }
+  }
+
+  if changed.is_empty() {
+    return Vec::new();
+  }
+
+  vec![Correction {
+    fixed_cmd: fixed_parts.join(" "),
+    reason: format!("wrong number of dashes ({})", changed.join(", ")),
+    confidence: 0.9,
+    source: CorrectionSource::Rule,
+  }]
+}
+
+// Returns the flag with one dash added or removed when only that form is known.
+// A `=value` suffix is kept as is.
+fn redash_flag(part: &str, flags: &[&str]) -> Option<String> {
+  if !part.starts_with('-') || part == "-" {
+    return None;
+  }
+
+  let (flag, value) = match part.split_once('=') {
+    Some((flag, value)) => (flag, Some(value)),
+    None => (part, None),
+  };
+
+  if flags.contains(&flag) {
+    return None;
+  }
+
+  // `ls -all` is `-a -l -l`, not `--all` missing a dash
+  if let Some(letters) = flag.strip_prefix('-').filter(|l| !l.starts_with('-')) {
+    if letters
+      .chars()
+      .all(|c| flags.contains(&format!("-{}", c).as_str()))
+    {
+      return None;
+    }
+  }
+
+  let candidate = match flag.strip_prefix("--") {
+    Some(name) => format!("-{}", name),
+    None => format!("-{}", flag),
+  };
+
+  if !flags.contains(&candidate.as_str()) {
+    return None;
+  }
+
+  Some(match value {
+    Some(value) => format!("{}={}", candidate, value),
+    None => candidate,
+  })
+}
+
+// `cat log > grep error` -> `cat log | grep error`
+fn redirect_into_command(cmd: &str) -> Vec<Correction> {
+  let mut parts: Vec<&str> = cmd.split_whitespace().collect();
+  let mut changed = Vec::new();
+  let mut quotes = 0;
+
+  for i in 1..parts.len().saturating_sub(1) {
+    // `echo "a > b"` is just text
+    quotes += parts[i - 1].matches(['"', '\'']).count();
+    if quotes % 2 == 1 {
+      continue;
+    }
+
+    let target = parts[i + 1];
+    // A path is a real redirect target, so is an existing file that happens to share a name
+    let is_program =
+      !target.contains(['/', '\\']) && !Path::new(target).exists() && command_exists(target);
+
+    if parts[i] == ">" && is_program {
+      parts[i] = "|";
+      changed.push(target);
+    }
+  }
+
+  if changed.is_empty() {
+    return Vec::new();
+  }
+
+  vec![Correction {
+    fixed_cmd: parts.join(" "),
+    reason: format!(
+      "'>' writes to a file named '{}', '|' pipes into it",
+      changed.join("', '")
+    ),
+    confidence: 0.9,
+    source: CorrectionSource::Rule,
+  }]
+}
+
+// `make 2>1` -> `make 2>&1`; without `&` the output goes to a file named `1`
+fn redirect_to_fd_number(cmd: &str) -> Vec<Correction> {
+  let mut parts: Vec<String> = cmd.split_whitespace().map(|p| p.to_string()).collect();
+  let mut changed = false;
+
+  for part in parts.iter_mut() {
+    if let [from @ (b'1' | b'2'), b'>', to @ (b'1' | b'2')] = part.as_bytes() {
+      if from != to {
+        *part = format!("{}>&{}", *from as char, *to as char);
+        changed = true;
+      }
+    }
+  }
+
+  if !changed {
+    return Vec::new();
+  }
+
+  vec![Correction {
+    fixed_cmd: parts.join(" "),
+    reason: "redirect to a file descriptor needs '>&'".to_string(),
+    confidence: 0.95,
+    source: CorrectionSource::Rule,
+  }]
+}
+
+// Only a near-certain match is worth offering; variables set in the shell but not
+// exported are invisible here.
+const ENV_VAR_MIN_SIMILARITY: f64 = 0.9;
+
+// `echo $PAHT` -> `echo $PATH`, `echo %USERPORFILE%` -> `echo %USERPROFILE%` (Windows),
+// matched against the variables that are actually set
+fn env_var_typo(cmd: &str) -> Vec<Correction> {
+  static PATTERN: OnceLock<Regex> = OnceLock::new();
+  let re = PATTERN.get_or_init(|| {
+    let pattern = if cfg!(windows) {
+      r"\$\{?([A-Za-z_][A-Za-z0-9_]*)|%([A-Za-z_][A-Za-z0-9_()]*)%"
+    } else {
+      r"\$\{?([A-Za-z_][A-Za-z0-9_]*)"
+    };
+    Regex::new(pattern).unwrap()
+  });
+  let names: Vec<String> = env::vars_os()
+    .filter_map(|(name, _)| name.into_string().ok())
+    .collect();
+
+  let mut fixed = cmd.to_string();
+  let mut renamed = Vec::new();
+  let mut confidence: f64 = 1.0;
+
+  // Right to left, so earlier byte offsets stay valid while replacing
+  let mut matches: Vec<_> = re.captures_iter(cmd).collect();
+  matches.reverse();
+
+  for caps in matches {
+    let (name, percent) = match (caps.get(1), caps.get(2)) {
+      (Some(name), _) => (name, false),
+      (None, Some(name)) => (name, true),
+      _ => continue,
+    };
+
+    // `'$HOME'` is literal text, and `FOO=1 ...; echo $FOO` sets its own
+    let in_single_quotes = cmd[..name.start()].matches('\'').count() % 2 == 1;
+    let assigned = cmd.contains(&format!("{}=", name.as_str()));
+    // Windows variable names are case-insensitive
+    let is_set = names
+      .iter()
+      .any(|n| n == name.as_str() || percent && n.eq_ignore_ascii_case(name.as_str()));
+    if in_single_quotes || assigned || is_set {
+      continue;
+    }
+
+    let closest = names
+      .iter()
+      .map(|n| {
+        let similarity = if percent {
+          jaro_winkler(&n.to_uppercase(), &name.as_str().to_uppercase())
+        } else {
+          jaro_winkler(n, name.as_str())
+        };
+        (n, similarity)
+      })
+      .filter(|(_, similarity)| *similarity >= ENV_VAR_MIN_SIMILARITY)
+      .max_by(|a, b| a.1.total_cmp(&b.1));
+
+    if let Some((real, similarity)) = closest {
+      fixed.replace_range(name.range(), real);
+      renamed.push(real.clone());
+      confidence = confidence.min(similarity);
+    }
+  }
+
+  if renamed.is_empty() {
+    return Vec::new();
+  }
+
+  renamed.reverse();
+  vec![Correction {
+    fixed_cmd: fixed,
+    reason: format!("unset variable, did you mean {}", renamed.join(", ")),
+    confidence,
+    source: CorrectionSource::Rule,
+  }]
+}
+
+// Flags that take one argument, typically text with spaces in it.
+const SINGLE_ARGUMENT_FLAGS: &[(&str, &[&str])] = &[
+  ("git", &["-m", "-am", "--message"]),
+  ("hg", &["-m", "--message"]),
+  ("svn", &["-m", "--message"]),
+  ("gh", &["-t", "--title", "-b", "--body"]),
+  ("sh", &["-c"]),
+  ("bash", &["-c"]),
+  ("zsh", &["-c"]),
+];
+
+// Words that end a flag's argument: another flag, or the shell taking over
+fn ends_argument(word: &str, is_command: bool) -> bool {
+  (word.starts_with('-') && !is_command)
+    || word.starts_with(['&', '|', ';', '<', '>'])
+    || word.starts_with("1>")
+    || word.starts_with("2>")
+}
+
+// `git commit -m fix the bug` -> `git commit -m "fix the bug"`; unquoted, only `fix` is the message
+fn quote_flag_argument(cmd: &str) -> Vec<Correction> {
+  // Already quoting something: leave the quoting to the user
+  if cmd.contains(['"', '\'', '`', '\\']) {
+    return Vec::new();
+  }
+
+  let parts: Vec<&str> = cmd.split_whitespace().collect();
+  let start = usize::from(parts.first() == Some(&"sudo"));
+  let Some(flags) = parts.get(start).and_then(|program| {
+    SINGLE_ARGUMENT_FLAGS
+      .iter()
+      .find(|(name, _)| name == program)
+      .map(|(_, flags)| *flags)
+  }) else {
+    return Vec::new();
+  };
+
+  let mut fixed: Vec<String> = Vec::new();
+  let mut quoted = Vec::new();
+  let mut i = 0;
+
+  while i < parts.len() {
+    let word = parts[i];
+    i += 1;
+
+    // `-m fix the bug` or `--message=fix the bug`
+    let (flag, first) = match word.split_once('=') {
+      Some((flag, value)) if flag.starts_with("--") && !value.is_empty() => (flag, Some(value)),
+      _ => (word, None),
+    };
+    if i <= start || !flags.contains(&flag) {
+      fixed.push(word.to_string());
+      continue;
+    }
+
+    // `sh -c ls -la`: a command string takes its flags along
+    let is_command = flag == "-c";
+    let words: Vec<&str> = first
+      .into_iter()
+      .chain(
+        parts[i..]
+          .iter()
+          .take_while(|word| !ends_argument(word, is_command))
+          .copied(),
+      )
+      .collect();
+
+    // `-m fix bug; git push`: where the argument ends is anyone's guess
+    if words.len() < 2 || words.iter().any(|word| word.contains(';')) {
+      fixed.push(word.to_string());
+      continue;
+    }
+
+    let text = words.join(" ");
+    // Single quotes keep `$HOME` and `!` literal, as they were meant to be read
+    let quote = if text.contains(['$', '!']) { '\'' } else { '"' };
+    let separator = if first.is_some() { "=" } else { " " };
+    fixed.push(format!("{}{}{}{}{}", flag, separator, quote, text, quote));
+    quoted.push(flag);
+    i += words.len() - usize::from(first.is_some());
+  }
+
+  if quoted.is_empty() {
+    return Vec::new();
+  }
+
+  vec![Correction {
+    fixed_cmd: fixed.join(" "),
+    reason: format!("quote the {} argument", quoted.join("/")),
+    confidence: 0.9,
+    source: CorrectionSource::Rule,
+  }]
+}
+
+// grep flags whose value is the next word, so it isn't mistaken for the pattern
+const GREP_VALUE_FLAGS: &[&str] = &[
+  "-A",
+  "-B",
+  "-C",
+  "-m",
+  "-d",
+  "-D",
+  "--after-context",
+  "--before-context",
+  "--context",
+  "--max-count",
+  "--include",
+  "--exclude",
+  "--exclude-dir",
+];
+
+// Index of grep's pattern word, or None when `-e`/`-f` supply it instead.
+fn grep_pattern_index(parts: &[&str], program: usize) -> Option<usize> {
+  let mut i = program + 1;
+  while let Some(word) = parts.get(i) {
+    if *word == "--" {
+      return Some(i + 1).filter(|i| *i < parts.len());
+    }
+    if !word.starts_with('-') || *word == "-" {
+      return Some(i);
+    }
+    let short = !word.starts_with("--");
+    if (short && word.contains(['e', 'f']))
+      || word.starts_with("--regexp")
+      || word.starts_with("--file")
+    {
+      return None;
+    }
+    i += if GREP_VALUE_FLAGS.contains(word) {
+      2
+    } else {
+      1
+    };
+  }
+  None
+}
+
+// Whether a flag already picks the regex flavour: -E, -P, -F, -G or their long forms.
+fn has_grep_syntax_flag(parts: &[&str], pattern: usize) -> bool {
+  parts[..pattern].iter().any(|word| {
+    if let Some(long) = word.strip_prefix("--") {
+      matches!(
+        long,
+        "extended-regexp" | "perl-regexp" | "fixed-strings" | "basic-regexp"
+      )
+    } else {
+      word.starts_with('-') && word.contains(['E', 'P', 'F', 'G'])
+    }
+  })
+}
+
+// `a|b` unquoted pipes into `b`. When `b` is a real program, that's what was meant.
+fn is_real_pipe(word: &str) -> bool {
+  word
+    .rsplit('|')
+    .next()
+    .is_some_and(|target| !target.is_empty() && command_exists(target))
+}
+
+// `grep -r TODO: fix.* src` -> `grep -r 'TODO: fix.*' src`, and
+// `grep 'error|warning' log` -> `grep -E 'error|warning' log`, since basic regex reads
+// `+`, `|` and `?` literally. Both are offered next to each other, never as the only fix.
+fn grep_pattern(cmd: &str) -> Vec<Correction> {
+  let parts: Vec<&str> = cmd.split_whitespace().collect();
+  let program = usize::from(parts.first() == Some(&"sudo"));
+  let Some(&name) = parts.get(program) else {
+    return Vec::new();
+  };
+  if !matches!(name, "grep" | "egrep" | "zgrep") {
+    return Vec::new();
+  }
+  let Some(pattern) = grep_pattern_index(&parts, program) else {
+    return Vec::new();
+  };
+
+  let mut corrections = Vec::new();
+  let mut fixed: Vec<String> = parts.iter().map(|word| word.to_string()).collect();
+
+  // The pattern runs through the last word with regex syntax that isn't a file.
+  // With quotes already in play, where it ends is the user's call.
+  if !cmd.contains(['"', '\'', '`', '\\']) {
+    let words: Vec<&str> = parts[pattern..]
+      .iter()
+      .take_while(|word| !ends_argument(word, false))
+      .copied()
+      .collect();
+    // With no quotes, the last word is the file even if it has a dot in it
+    let searchable = if words.len() > 1 {
+      &words[..words.len() - 1]
+    } else {
+      &words[..]
+    };
+    let span = searchable
+      .iter()
+      .rposition(|word| {
+        word.contains([
+          '.', '*', '+', '?', '|', '(', ')', '[', ']', '{', '}', '^', '$',
+        ]) && !Path::new(word).exists()
+      })
+      .map(|last| last + 1);
+
+    let needs_quotes = span.is_some_and(|span| {
+      let words = &words[..span];
+      !words.iter().any(|word| is_real_pipe(word))
+        && (span > 1
+          || words[0].contains(['*', '?', '|', '(', ')', '[', ']', '{', '}', '$', '<', '>']))
+    });
+
+    if let (Some(span), true) = (span, needs_quotes) {
+      fixed.splice(
+        pattern..pattern + span,
+        [format!("'{}'", words[..span].join(" "))],
+      );
+      corrections.push(Correction {
+        fixed_cmd: fixed.join(" "),
+        reason: "quote the grep pattern".to_string(),
+        confidence: 0.8,
+        source: CorrectionSource::Rule,
+      });
+    }
+  }
+
+  // `\+` is GNU basic regex for the operator, so only bare ones count
+  // An unquoted `|` is the shell's
+  let text = &fixed[pattern];
+  let quoted = text.starts_with(['\'', '"']);
+  let operators: Vec<char> = ['+', '|', '?']
+    .into_iter()
+    .filter(|op| quoted || *op != '|')
+    .filter(|op| {
+      text
+        .match_indices(*op)
+        .any(|(i, _)| !text[..i].ends_with('\\'))
+    })
+    .collect();
+  if name != "egrep" && !operators.is_empty() && !has_grep_syntax_flag(&parts, pattern) {
+    // Unless quoted just above, keep the command as typed; quotes may hold spaces
+    let fixed_cmd = if corrections.is_empty() {
+      let after = cmd.find(name).unwrap_or(0) + name.len();
+      format!("{} -E{}", &cmd[..after], &cmd[after..])
+    } else {
+      fixed.insert(program + 1, "-E".to_string());
+      fixed.join(" ")
+    };
+    let listed: Vec<String> = operators.iter().map(|op| format!("'{}'", op)).collect();
+    corrections.push(Correction {
+      fixed_cmd,
+      reason: format!(
+        "extended regex: basic grep reads {} literally",
+        listed.join("/")
+      ),
+      confidence: 0.75,
+      source: CorrectionSource::Rule,
+    });
+  }
+
+  corrections
+}
+
+// docker run/create/exec options that take a value, skipped over to find the image
+const DOCKER_VALUE_FLAGS: &[&str] = &[
+  "-e",
+  "--env",
+  "--env-file",
+  "-h",
+  "--hostname",
+  "-l",
+  "--label",
+  "--mount",
+  "--name",
+  "--network",
+  "-p",
+  "--publish",
+  "--platform",
+  "-u",
+  "--user",
+  "-v",
+  "--volume",
+  "-w",
+  "--workdir",
+  "--entrypoint",
+];
+
+const DOCKER_SWITCHES: &[&str] = &[
+  "--rm",
+  "--init",
+  "--privileged",
+  "--detach",
+  "--interactive",
+  "--tty",
+];
+
+// `-it`, `-d`, `--rm`: docker options that take no value
+fn is_docker_switch(word: &str) -> bool {
+  if DOCKER_SWITCHES.contains(&word) {
+    return true;
+  }
+  word
+    .strip_prefix('-')
+    .is_some_and(|letters| !letters.is_empty() && letters.chars().all(|c| "itd".contains(c)))
+}
+
+// After the image, a bare `-d`, `-i` or `--init` is as likely the entrypoint's own flag
+// (`docker run myapp -d`), so only these unmistakably-docker ones are moved in front of it
+const DOCKER_MOVABLE_SWITCHES: &[&str] = &["-it", "-ti", "--rm", "--interactive", "--tty"];
+
+const TAR_EXTENSIONS: &[&str] = &[
+  ".tar", ".tar.gz", ".tgz", ".tar.bz2", ".tbz", ".tbz2", ".tar.xz", ".txz", ".tar.zst",
+];
+
+// `docker run myimg -it` -> `docker run -it myimg`, `tar file.tar -xvf` -> `tar -xvf file.tar`.
+// Only options that are almost never meant for the container (or a tar member) are moved.
+fn misplaced_flags(cmd: &str) -> Vec<Correction> {
+  let parts: Vec<&str> = cmd.split_whitespace().collect();
+  let start = usize::from(parts.first() == Some(&"sudo"));
+
+  let fixed = match parts.get(start..) {
+    Some(["docker", sub @ ("run" | "create" | "exec"), ..]) => {
+      docker_flags_after_image(&parts, start + 2).map(|fixed| {
+        let target = if *sub == "exec" { "container" } else { "image" };
+        (
+          fixed,
+          format!("docker {} options go before the {}", sub, target),
+        )
+      })
+    }
+    Some(["tar", archive, letters, ..]) => tar_flags_after_archive(archive, letters).then(|| {
+      let mut fixed = parts.clone();
+      fixed.swap(start + 1, start + 2);
+      (fixed, "tar options go before the archive name".to_string())
+    }),
+    _ => None,
+  };
+
+  let Some((fixed, reason)) = fixed else {
+    return Vec::new();
+  };
+
+  vec![Correction {
+    fixed_cmd: fixed.join(" "),
+    reason,
+    confidence: 0.8,
+    source: CorrectionSource::Rule,
+  }]
+}
+
+// Moves the switches right after the image (or container) in front of it. Anything
+// after them stays where it is, since that's the command to run inside.
+fn docker_flags_after_image<'a>(parts: &[&'a str], options: usize) -> Option<Vec<&'a str>> {
+  let mut image = options;
+  while let Some(word) = parts.get(image).filter(|word| word.starts_with('-')) {
+    if is_docker_switch(word) || word.contains('=') {
+      image += 1;
+    } else if DOCKER_VALUE_FLAGS.contains(word) {
+      image += 2;
+    } else {
+      // An option we don't know; can't tell where the image is
+      return None;
+    }
+  }
+
+  let mut end = image + 1;
+  while parts
+    .get(end)
+    .is_some_and(|word| DOCKER_MOVABLE_SWITCHES.contains(word))
+  {
+    end += 1;
+  }
+  if end == image + 1 || image >= parts.len() {
+    return None;
+  }
+
+  let mut fixed = parts[..image].to_vec();
+  fixed.extend_from_slice(&parts[image + 1..end]);
+  fixed.push(parts[image]);
+  fixed.extend_from_slice(&parts[end..]);
+  Some(fixed)
+}
+
+// `tar file.tar -xvf`: an archive name followed by an option cluster ending in `f`,
+// which then has nothing to name
+fn tar_flags_after_archive(archive: &str, letters: &str) -> bool {
+  let is_archive = TAR_EXTENSIONS.iter().any(|ext| archive.ends_with(ext));
+  let Some(letters) = letters.strip_prefix('-') else {
+    return false;
+  };
+
+  is_archive
+    && letters.ends_with('f')
+    && letters.chars().all(|c| c.is_ascii_alphabetic())
+    && letters.chars().any(|c| "cxtru".contains(c))
+}
+
+// `apt isntall curl` -> `apt install curl`, `sudo aptinstall curl` -> `sudo apt install curl`
+fn package_manager_verb(cmd: &str) -> Vec<Correction> {
+  let mut parts: Vec<&str> = cmd.split_whitespace().collect();
+  let start = usize::from(parts.first() == Some(&"sudo"));
+  let Some(&word) = parts.get(start) else {
+    return Vec::new();
+  };
+
+  let mut managers = get_package_manager_verbs();
+  // `apt-getinstall` must be split after `apt-get`, not `apt`
+  managers.sort_by_key(|(manager, _)| std::cmp::Reverse(manager.len()));
+
+  for (manager, verbs) in &managers {
+    let (typed, glued) = if word == *manager {
+      match parts.get(start + 1) {
+        Some(verb) if !verb.starts_with('-') && !verbs.contains(verb) => (*verb, false),
+        _ => return Vec::new(),
+      }
+    } else if let Some(rest) = word.strip_prefix(manager).filter(|rest| !rest.is_empty()) {
+      (rest, true)
+    } else {
+      continue;
+    };
+
+    let closest = verbs
+      .iter()
+      .map(|verb| (*verb, jaro_winkler(typed, verb)))
+      .filter(|(_, similarity)| *similarity > 0.8)
+      .max_by(|a, b| a.1.total_cmp(&b.1));
+
+    let Some((verb, similarity)) = closest else {
+      return Vec::new();
+    };
+
+    let joined = format!("{} {}", manager, verb);
+    if glued {
+      parts[start] = &joined;
+    } else {
+      parts[start + 1] = verb;
+    }
+
+    return vec![Correction {
+      fixed_cmd: parts.join(" "),
+      reason: format!("{} command '{}'", manager, verb),
+      confidence: similarity.min(0.95),
+      source: CorrectionSource::Rule,
+    }];
+  }
+
+  Vec::new()
+}
+
+// Short forms the tools accept, too short to be worth suggesting
+const DEV_TOOL_ALIASES: &[(&str, &[&str])] = &[
+  ("cargo", &["b", "c", "d", "r", "t", "rm"]),
+  (
+    "npm",
+    &[
+      "i", "it", "add", "cit", "ln", "ls", "r", "rb", "rm", "un", "up", "t", "x", "tst",
+    ],
+  ),
+  ("yarn", &["rm"]),
+  ("pnpm", &["i", "ln", "ls", "rb", "rm", "un", "up", "t", "x"]),
+];
+
+// `cargo buidl --release` -> `cargo build --release`, `npm run biuld` -> `npm run build`,
+// `yarn tset` -> `yarn test` when package.json has a `test` script, and
+// `yarn install lodash` -> `yarn add lodash`
+fn dev_tool_subcommand(cmd: &str) -> Vec<Correction> {
+  let parts: Vec<&str> = cmd.split_whitespace().collect();
+  let (tool, word) = match parts.as_slice() {
+    [tool @ ("cargo" | "npm" | "yarn" | "pnpm"), word, ..] if !word.starts_with('-') => {
+      (*tool, *word)
+    }
+    _ => return Vec::new(),
+  };
+
+  let verbs = get_subcommands()
+    .into_iter()
+    .find(|(name, _)| *name == tool)
+    .map(|(_, verbs)| verbs)
+    .unwrap_or_default();
+  let is_alias = DEV_TOOL_ALIASES
+    .iter()
+    .any(|(name, aliases)| *name == tool && aliases.contains(&word));
+  let scripts = if tool == "cargo" {
+    &[][..]
+  } else {
+    package_scripts()
+  };
+
+  // yarn 1 refuses `install <pkg>` and says to use `add`
+  if tool == "yarn" && word == "install" && parts.len() > 2 && !parts[2].starts_with('-') {
+    return dev_tool_fix(
+      cmd,
+      2,
+      "yarn add",
+      "'yarn install' doesn't take packages, 'yarn add' does".to_string(),
+      0.9,
+    );
+  }
+
+  // `npm run biuld`: the script name is what's mistyped
+  if matches!(word, "run" | "run-script") {
+    let Some(&script) = parts.get(2) else {
+      return Vec::new();
+    };
+    if script.starts_with('-') || scripts.iter().any(|s| s == script) {
+      return Vec::new();
+    }
+    return match closest_word(script, scripts.iter().map(String::as_str)) {
+      Some((fixed, similarity)) => dev_tool_fix(
+        cmd,
+        3,
+        &format!("{} {} {}", tool, word, fixed),
+        format!("package.json script '{}'", fixed),
+        similarity,
+      ),
+      None => Vec::new(),
+    };
+  }
+
+  // A real subcommand, or a third-party one like `cargo nextest`
+  if verbs.contains(&word)
+    || is_alias
+    || (tool == "cargo" && command_exists(&format!("cargo-{}", word)))
+  {
+    return Vec::new();
+  }
+
+  // yarn and pnpm run scripts by name; npm needs `run` in front of anything but
+  // start/test/stop/restart
+  if scripts.iter().any(|s| s == word) {
+    if tool != "npm" {
+      return Vec::new();
+    }
+    return dev_tool_fix(
+      cmd,
+      2,
+      &format!("npm run {}", word),
+      format!("npm runs package.json scripts with 'npm run {}'", word),
+      0.9,
+    );
+  }
+
+  let verb = closest_word(word, verbs.iter().copied()).map(|(verb, similarity)| {
+    (
+      format!("{} {}", tool, verb),
+      format!("{} command '{}'", tool, verb),
+      similarity,
+    )
+  });
+  // `npm test` already runs the `test` script
+  let runnable = scripts
+    .iter()
+    .map(String::as_str)
+    .filter(|script| tool != "npm" || !verbs.contains(script));
+  let script = closest_word(word, runnable).map(|(script, similarity)| {
+    let prefix = if tool == "npm" { "npm run" } else { tool };
+    (
+      format!("{} {}", prefix, script),
+      format!("package.json script '{}'", script),
+      similarity,
+    )
+  });
+
+  [verb, script]
+    .into_iter()
+    .flatten()
+    .flat_map(|(replacement, reason, similarity)| {
+      dev_tool_fix(cmd, 2, &replacement, reason, similarity)
+    })
+    .collect()
+}
+
+fn closest_word<'a>(
+  word: &str,
+  candidates: impl Iterator<Item = &'a str>,
+) -> Option<(&'a str, f64)> {
+  candidates
+    .map(|candidate| (candidate, jaro_winkler(word, candidate)))
+    .filter(|(_, similarity)| *similarity > 0.8)
+    .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+// Swaps the first `replaced_words` words for `replacement`, keeping the rest as typed.
+fn dev_tool_fix(
+  cmd: &str,
+  replaced_words: usize,
+  replacement: &str,
+  reason: String,
+  similarity: f64,
+) -> Vec<Correction> {
+  let mut rest = cmd.trim_start();
+  for word in cmd.split_whitespace().take(replaced_words) {
+    rest = rest[word.len()..].trim_start();
+  }
+  let fixed_cmd = if rest.is_empty() {
+    replacement.to_string()
+  } else {
+    format!("{} {}", replacement, rest)
+  };
+
+  vec![Correction {
+    fixed_cmd,
+    reason,
+    confidence: similarity.min(0.95),
+    source: CorrectionSource::Rule,
+  }]
+}
+
+// Script names from ./package.json, read once per run.
+fn package_scripts() -> &'static [String] {
+  static SCRIPTS: OnceLock<Vec<String>> = OnceLock::new();
+
+  SCRIPTS.get_or_init(|| {
+    std::fs::read_to_string("package.json")
+      .ok()
+      .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+      .and_then(|package| {
+        package["scripts"]
+          .as_object()
+          .map(|scripts| scripts.keys().cloned().collect())
+      })
+      .unwrap_or_default()
+  })
+}
+
+// `python script.py` on a system that only ships `python3` -> `python3 script.py`
+fn python3_fallback(cmd: &str) -> Vec<Correction> {
+  let program = match cmd.split_whitespace().next() {
+    Some(p) if p == "python" || p == "pip" => p,
+    _ => return Vec::new(),
+  };
+
+  let versioned = format!("{}3", program);
+  if command_exists(program) || !command_exists(&versioned) {
+    return Vec::new();
+  }
+
+  let rest = &cmd.trim_start()[program.len()..];
+
+  vec![Correction {
+    fixed_cmd: format!("{}{}", versioned, rest),
+    reason: format!("'{}' is not installed, but '{}' is", program, versioned),
+    confidence: 0.95,
+    source: CorrectionSource::Rule,
+  }]
+}
+
+// `docker-compose up` -> `docker compose up` when only the Compose plugin is installed,
+// and the other way round when only the standalone binary is.
+fn docker_compose_form(cmd: &str) -> Vec<Correction> {
+  let parts: Vec<&str> = cmd.split_whitespace().collect();
+  let (prefix_words, replacement, reason) = match parts.as_slice() {
+    ["docker-compose", ..] => {
+      if command_exists("docker-compose") || !compose_plugin_installed() {
+        return Vec::new();
+      }
+      (
+        1,
+        "docker compose",
+        "'docker-compose' is not installed, but the 'docker compose' plugin is",
+      )
+    }
+    ["docker", "compose", ..] => {
+      if !command_exists("docker-compose") || compose_plugin_installed() {
+        return Vec::new();
+      }
+      (
+        2,
+        "docker-compose",
+        "the 'docker compose' plugin is not installed, but 'docker-compose' is",
+      )
+    }
+    _ => return Vec::new(),
+  };
+
+  // Keep the arguments exactly as typed, quotes and all
+  let mut rest = cmd.trim_start();
+  for word in &parts[..prefix_words] {
+    rest = rest[word.len()..].trim_start();
+  }
+  let fixed_cmd = if rest.is_empty() {
+    replacement.to_string()
+  } else {
+    format!("{} {}", replacement, rest)
+  };
+
+  vec![Correction {
+    fixed_cmd,
+    reason: reason.to_string(),
+    confidence: 0.95,
+    source: CorrectionSource::Rule,
+  }]
+}
+
+// Whether `docker compose` works, asked from docker once per run.
+fn compose_plugin_installed() -> bool {
+  static INSTALLED: OnceLock<bool> = OnceLock::new();
+
+  *INSTALLED.get_or_init(|| {
+    command_exists("docker")
+      && Command::new("docker")
+        .args(["compose", "version"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+  })
+}
+
+// `rm notes.txt` -> `trash-put notes.txt`, offered next to the `rm` itself
+fn rm_to_trash(cmd: &str) -> Vec<Correction> {
+  trash_alternative(cmd, 0.9).into_iter().collect()
+}
+
+/// The trash-tool version of a plain `rm <files>`, when a trash tool is installed.
+/// Also applied to fixes that turn out to be an `rm` (`rn foo` -> `rm foo`).
+pub fn trash_alternative(cmd: &str, confidence: f64) -> Option<Correction> {
+  let parts: Vec<&str> = cmd.split_whitespace().collect();
+  if parts.first() != Some(&"rm") {
+    return None;
+  }
+
+  let args = &parts[1..];
+  // `rm -i` already asks; anything past these flags isn't a plain delete
+  let flags_ok = args.iter().filter(|arg| arg.starts_with('-')).all(|arg| {
+    matches!(
+      *arg,
+      "-r" | "-R" | "-f" | "-rf" | "-fr" | "-Rf" | "-fR" | "--recursive" | "--force" | "--"
+    )
+  });
+  let files: Vec<&str> = args
+    .iter()
+    .filter(|arg| !arg.starts_with('-'))
+    .copied()
+    .collect();
+  if !flags_ok || files.is_empty() {
+    return None;
+  }
+
+  let tool = trash_tool()?;
+  // trash-put accepts rm's flags and ignores them; the others would reject them
+  let rest = if tool == "trash-put" {
+    args.join(" ")
+  } else {
+    files.join(" ")
+  };
+
+  Some(Correction {
+    fixed_cmd: format!("{} {}", tool, rest),
+    reason: "safer: move to trash".to_string(),
+    confidence,
+    source: CorrectionSource::Rule,
+  })
+}
+
+/// Whether `cmd` runs the trash tool `trash_alternative` rewrites to.
+pub fn is_trash_command(cmd: &str) -> bool {
+  trash_tool().is_some_and(|tool| {
+    cmd
+      .strip_prefix(tool)
+      .is_some_and(|rest| rest.starts_with(' '))
+  })
+}
+
+// trash-cli, then macOS 14's `trash`, then GNOME's `gio trash`. Looked up once per run.
+fn trash_tool() -> Option<&'static str> {
+  static TOOL: OnceLock<Option<&'static str>> = OnceLock::new();
+
+  *TOOL.get_or_init(|| {
+    if command_exists("trash-put") {
+      Some("trash-put")
+    } else if command_exists("trash") {
+      Some("trash")
+    } else if command_exists("gio") {
+      Some("gio trash")
+    } else {
+      None
+    }
+  })
+}
+
+// `git checkout mian` -> `git checkout main`, matched against the repo's real branches
+fn git_branch_typo(cmd: &str) -> Vec<Correction> {
+  let parts: Vec<&str> = cmd.split_whitespace().collect();
+  if parts.len() < 3 || parts[0] != "git" || !matches!(parts[1], "checkout" | "switch") {
+    return Vec::new();
+  }
+
+  let target = parts[2];
+  if target.starts_with('-') || Path::new(target).exists() {
+    return Vec::new();
+  }
+
+  let Some(branches) = git_branches() else {
+    return Vec::new();
+  };
+
+  if branches.iter().any(|b| b == target) {
+    return Vec::new();
+  }
+
+  let closest = branches
+    .iter()
+    .map(|branch| (branch, jaro_winkler(target, branch)))
+    .filter(|(_, similarity)| *similarity > 0.8)
+    .max_by(|a, b| a.1.total_cmp(&b.1));
+
+  let Some((branch, similarity)) = closest else {
+    return Vec::new();
+  };
+
+  let mut fixed = parts.clone();
+  fixed[2] = branch;
+
+  vec![Correction {
+    fixed_cmd: fixed.join(" "),
+    reason: format!("branch '{}' exists", branch),
+    confidence: similarity,
+    source: CorrectionSource::Rule,
+  }]
+}
+
+// Programs whose first argument is a file that should already exist. Nothing that
+// deletes or overwrites: pointing `rm` at a different real file is worse than the typo.
+const FILE_ARGUMENT_COMMANDS: &[&str] = &[
+  "cat", "less", "more", "head", "tail", "bat", "vim", "vi", "nvim", "nano", "emacs", "code",
+  "source", "wc", "open",
+];
+
+// `cat myfle.txt` -> `cat myfile.txt`, matched against the files next to the missing one
+fn file_argument_typo(cmd: &str) -> Vec<Correction> {
+  let parts: Vec<&str> = cmd.split_whitespace().collect();
+  if parts.len() < 2 || !FILE_ARGUMENT_COMMANDS.contains(&parts[0]) {
+    return Vec::new();
+  }
+
+  let Some(index) = parts
+    .iter()
+    .skip(1)
+    .position(|p| !p.starts_with('-'))
+    .map(|i| i + 1)
+  else {
+    return Vec::new();
+  };
+  let target = parts[index];
+
+  // Globs, variables, and `~` are the shell's job, not a literal path
+  if target.contains(['*', '?', '$', '~', '\'', '"']) || Path::new(target).exists() {
+    return Vec::new();
+  }
+
+  let Some(name) = Path::new(target).file_name().and_then(|n| n.to_str()) else {
+    return Vec::new();
+  };
+  let Some(prefix) = target.strip_suffix(name) else {
+    return Vec::new();
+  };
+  let dir = if prefix.is_empty() { "." } else { prefix };
+
+  let Ok(entries) = std::fs::read_dir(dir) else {
+    return Vec::new();
+  };
+
+  let closest = entries
+    .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+    .map(|entry| {
+      let similarity = jaro_winkler(name, &entry);
+      (entry, similarity)
+    })
+    .filter(|(_, similarity)| *similarity > 0.85)
+    .max_by(|a, b| a.1.total_cmp(&b.1));
+
+  let Some((entry, similarity)) = closest else {
+    return Vec::new();
+  };
+
+  let path = format!("{}{}", prefix, entry);
+  let mut fixed = parts.clone();
+  fixed[index] = &path;
+
+  vec![Correction {
+    fixed_cmd: fixed.join(" "),
+    reason: format!("'{}' exists", path),
+    confidence: similarity,
+    source: CorrectionSource::Rule,
+  }]
+}
+
+// Local branch names of the current repo, asked from git once per run.
+// None outside a repo or when git isn't installed.
+fn git_branches() -> Option<&'static Vec<String>> {
+  static BRANCHES: OnceLock<Option<Vec<String>>> = OnceLock::new();
+
+  BRANCHES
+    .get_or_init(|| {
+      let output = Command::new("git")
+        .args(["branch", "--format=%(refname:short)"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+      if !output.status.success() {
+        return None;
+      }
+
+      Some(
+        String::from_utf8_lossy(&output.stdout)
+          .lines()
+          .map(|line| line.trim().to_string())
+          .filter(|line| !line.is_empty())
+          .collect(),
+      )
+    })
+    .as_ref()
+}
+
+// `./script.sh` failing with "Permission denied" -> `chmod +x ./script.sh && ./script.sh`
+#[cfg(unix)]
+fn missing_executable_bit(cmd: &str) -> Vec<Correction> {
+  use std::fs;
+  use std::os::unix::fs::PermissionsExt;
+
+  let program = match cmd.split_whitespace().next() {
+    Some(p) if p.starts_with("./") => p,
+    _ => return Vec::new(),
+  };
+
+  let metadata = match fs::metadata(program) {
+    Ok(m) if m.is_file() => m,
+    _ => return Vec::new(),
+  };
+
+  if metadata.permissions().mode() & 0o111 != 0 {
+    return Vec::new();
+  }
+
+  vec![Correction {
+    fixed_cmd: format!("chmod +x {} && {}", program, cmd),
+    reason: "file is not executable".to_string(),
+    confidence: 0.95,
+    source: CorrectionSource::Rule,
+  }]
+}
+
+#[cfg(not(unix))]
+fn missing_executable_bit(_cmd: &str) -> Vec<Correction> {
+  Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn redash_flag_keeps_clusters_of_short_flags() {
+    let flags = ["-a", "-l", "--all", "--color"];
+    assert_eq!(redash_flag("-all", &flags), None);
+    assert_eq!(redash_flag("-la", &flags), None);
+  }
+
+  #[test]
+  fn redash_flag_fixes_a_missing_or_extra_dash() {
+    let flags = ["-a", "-l", "-m", "--all", "--color"];
+    assert_eq!(redash_flag("-color", &flags), Some("--color".to_string()));
+    assert_eq!(
+      redash_flag("-color=auto", &flags),
+      Some("--color=auto".to_string())
+    );
+    assert_eq!(redash_flag("--m", &flags), Some("-m".to_string()));
+  }
+}
@@ -0,0 +1,65 @@
+use crate::config::wtf_dir;
+use crate::error::WtfError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+// How often each suggested fix was shown and accepted, used to rank suggestions.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AcceptanceStats {
+  #[serde(default)]
+  pub shown: HashMap<String, u32>,
+  #[serde(default)]
+  pub accepted: HashMap<String, u32>,
+}
+
+impl AcceptanceStats {
+  pub fn load() -> Self {
+    stats_path()
+      .ok()
+      .and_then(|path| fs::read_to_string(path).ok())
+      .and_then(|content| serde_json::from_str(&content).ok())
+      .unwrap_or_default()
+  }
+
+  pub fn save(&self) -> Result<(), WtfError> {
+    let path = stats_path()?;
+
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent)
+        .map_err(|e| WtfError::ConfigError(format!("Failed to create config directory: {}", e)))?;
+    }
+
+    let content = serde_json::to_string(self)
+      .map_err(|e| WtfError::ConfigError(format!("Failed to serialize stats: {}", e)))?;
+
+    fs::write(&path, content)
+      .map_err(|e| WtfError::ConfigError(format!("Failed to write stats: {}", e)))
+  }
+
+  /// Fraction of times this fix was picked when offered, if it was ever offered.
+  pub fn acceptance_rate(&self, fixed_cmd: &str) -> Option<f64> {
+    let shown = *self.shown.get(fixed_cmd)?;
+    if shown == 0 {
+      return None;
+    }
+
+    let accepted = self.accepted.get(fixed_cmd).copied().unwrap_or(0);
+    Some((accepted as f64 / shown as f64).min(1.0))
+  }
+
+  pub fn record_shown<'a>(&mut self, fixed_cmds: impl IntoIterator<Item = &'a str>) {
+    for cmd in fixed_cmds {
+      *self.shown.entry(cmd.to_string()).or_insert(0) += 1;
+    }
+  }
+
+  pub fn record_accepted(&mut self, fixed_cmd: &str) {
+    *self.accepted.entry(fixed_cmd.to_string()).or_insert(0) += 1;
+  }
+}
+
+fn stats_path() -> Result<PathBuf, WtfError> {
+  Ok(wtf_dir()?.join("stats.json"))
+}
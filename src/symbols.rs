@@ -0,0 +1,100 @@
+// Every emoji and symbol the UI prints, with an ASCII stand-in for terminals
+// (and log collectors) that would show boxes or mojibake instead.
+
+use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ASCII_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// `ascii_only` from the config; None means decide from the environment.
+pub fn init(ascii_only: Option<bool>) {
+  let ascii = ascii_only.unwrap_or_else(|| !terminal_supports_unicode());
+  ASCII_ONLY.store(ascii, Ordering::Relaxed);
+}
+
+fn terminal_supports_unicode() -> bool {
+  // Windows consoles have handled these since Windows 10; set `ascii_only` otherwise.
+  if cfg!(target_os = "windows") {
+    return true;
+  }
+
+  // The Linux virtual console has no emoji glyphs.
+  if env::var("TERM").map(|t| t == "linux").unwrap_or(false) {
+    return false;
+  }
+
+  ["LC_ALL", "LC_CTYPE", "LANG"]
+    .iter()
+    .filter_map(|name| env::var(name).ok())
+    .find(|value| !value.is_empty())
+    .map(|locale| {
+      let locale = locale.to_lowercase();
+      locale.contains("utf-8") || locale.contains("utf8")
+    })
+    .unwrap_or(false)
+}
+
+fn pick(unicode: &'static str, ascii: &'static str) -> &'static str {
+  if ASCII_ONLY.load(Ordering::Relaxed) {
+    ascii
+  } else {
+    unicode
+  }
+}
+
+pub fn ok() -> &'static str {
+  pick("✓", "[OK]")
+}
+
+pub fn fail() -> &'static str {
+  pick("✗", "[X]")
+}
+
+pub fn error() -> &'static str {
+  pick("❌", "[X]")
+}
+
+pub fn warn() -> &'static str {
+  pick("⚠️ ", "[!]")
+}
+
+pub fn info() -> &'static str {
+  pick("ℹ", "[i]")
+}
+
+pub fn tip() -> &'static str {
+  pick("💡", "[TIP]")
+}
+
+pub fn ai() -> &'static str {
+  pick("🤖", "[AI]")
+}
+
+pub fn welcome() -> &'static str {
+  pick("🎉", "***")
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn note() -> &'static str {
+  pick("📝", "[NOTE]")
+}
+
+pub fn docs() -> &'static str {
+  pick("📖", "[DOCS]")
+}
+
+pub fn shrug() -> &'static str {
+  pick("¯\\_(ツ)_/¯", "(?)")
+}
+
+pub fn arrow() -> &'static str {
+  pick("→", "->")
+}
+
+pub fn bullet() -> &'static str {
+  pick("•", "-")
+}
+
+pub fn ellipsis() -> &'static str {
+  pick("…", "...")
+}
@@ -1,50 +1,264 @@
-use crate::corrections::Correction;
+use crate::commands::BuiltinFix;
+use crate::config::{ConfigCheck, CustomTypo, MatchMode, TypoIssue};
+use crate::corrections::{dropped_words, Correction};
+use crate::history::HistoryEntry;
+use crate::path::command_exists;
+use crate::symbols;
 use colored::*;
-use std::io::{self, Write};
+use std::env;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// In eval mode stdout is reserved for the fixed command, so everything else goes to stderr.
+static OUTPUT_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+pub fn set_output_to_stderr(enabled: bool) {
+  OUTPUT_TO_STDERR.store(enabled, Ordering::Relaxed);
+}
+
+pub fn output_to_stderr() -> bool {
+  OUTPUT_TO_STDERR.load(Ordering::Relaxed)
+}
+
+// `--quiet`: only suggestions, prompts, results, and errors are printed.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+pub fn set_quiet(enabled: bool) {
+  QUIET.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+  QUIET.load(Ordering::Relaxed)
+}
+
+/// Decides once whether to emit ANSI colors, based on the stream we write to.
+///
+/// Colors are disabled for non-terminals, `TERM=dumb`, `NO_COLOR`, and Windows
+/// consoles where VT processing can't be enabled. `CLICOLOR_FORCE` overrides all of them.
+pub fn init_colors() {
+  let forced = env::var("CLICOLOR_FORCE")
+    .map(|v| v != "0")
+    .unwrap_or(false);
+
+  let is_terminal = if output_to_stderr() {
+    io::stderr().is_terminal()
+  } else {
+    io::stdout().is_terminal()
+  };
+  let is_dumb = env::var("TERM").map(|t| t == "dumb").unwrap_or(false);
+  let no_color = env::var_os("NO_COLOR").is_some();
+
+  let enabled = forced || (is_terminal && !is_dumb && !no_color && enable_ansi_support());
+  colored::control::set_override(enabled);
+}
+
+#[cfg(windows)]
+fn enable_ansi_support() -> bool {
+  colored::control::set_virtual_terminal(true).is_ok()
+}
+
+#[cfg(not(windows))]
+fn enable_ansi_support() -> bool {
+  true
+}
+
+// Shared with the other modules, so eval mode's stderr routing and `--quiet` apply
+// to everything wtf prints.
+macro_rules! out {
+  ($($arg:tt)*) => {
+    if $crate::ui::output_to_stderr() {
+      eprintln!($($arg)*)
+    } else {
+      println!($($arg)*)
+    }
+  };
+}
+
+// Headers, tips, and spacing: everything `--quiet` drops.
+macro_rules! decor {
+  ($($arg:tt)*) => {
+    if !$crate::ui::is_quiet() {
+      $crate::ui::out!($($arg)*)
+    }
+  };
+}
+
+macro_rules! out_inline {
+  ($($arg:tt)*) => {
+    if $crate::ui::output_to_stderr() {
+      eprint!($($arg)*);
+      std::io::Write::flush(&mut std::io::stderr()).unwrap();
+    } else {
+      print!($($arg)*);
+      std::io::Write::flush(&mut std::io::stdout()).unwrap();
+    }
+  };
+}
+
+pub(crate) use {decor, out};
 
 pub fn display_corrections(last_cmd: &str, corrections: &[Correction]) {
-  println!("{}", "Previous command:".bright_red());
-  println!("  {}", last_cmd.bright_yellow());
-  println!();
+  decor!("{}", "Previous command:".bright_red());
+  decor!("  {}", last_cmd.bright_yellow());
+  decor!();
 
-  for (i, correction) in corrections.iter().enumerate() {
-    println!(
-      "{} {} {} {}",
+  let suggestions: Vec<(&str, &str)> = corrections
+    .iter()
+    .map(|c| (c.fixed_cmd.as_str(), c.reason.as_str()))
+    .collect();
+  display_suggestions(last_cmd, &suggestions);
+}
+
+/// The numbered `(fix, reason)` list that `prompt_selection` picks from.
+pub fn display_suggestions(last_cmd: &str, suggestions: &[(&str, &str)]) {
+  for (i, (fixed_cmd, reason)) in suggestions.iter().enumerate() {
+    out!(
+      "{} {} {} {}{}",
       format!("[{}]", i + 1).bright_cyan(),
       "Suggested fix:".bright_green(),
+      fixed_cmd.bright_white().bold(),
+      format!("({})", reason).dimmed(),
+      dropped_badge(last_cmd, fixed_cmd)
+    );
+  }
+  decor!();
+}
+
+fn dropped_badge(cmd: &str, fixed: &str) -> String {
+  let dropped = dropped_words(cmd, fixed);
+  if dropped.is_empty() {
+    return String::new();
+  }
+
+  format!(
+    " {}",
+    format!("{} removes {}", symbols::warn(), quote_words(&dropped)).yellow()
+  )
+}
+
+fn quote_words(words: &[&str]) -> String {
+  words
+    .iter()
+    .map(|word| format!("'{}'", word))
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+/// Asked even with `--yes`: a fix that deletes words may no longer do what was typed.
+pub fn confirm_dropped_words(dropped: &[&str]) -> bool {
+  out!(
+    "{}",
+    format!(
+      "{} This fix removes {} from your command.",
+      symbols::warn(),
+      quote_words(dropped)
+    )
+    .yellow()
+  );
+  prompt_yes_no("Run it anyway?", false)
+}
+
+// A reflexive Enter or `y` shouldn't be enough for a fix that can't be taken back.
+pub fn confirm_dangerous(cmd: &str, reason: &str) -> bool {
+  out!(
+    "{}",
+    format!("{} This command {}:", symbols::warn(), reason).yellow()
+  );
+  out!("  {}", cmd.bright_white().bold());
+  out_inline!("{} ", "Type 'yes' to run it:".bright_cyan());
+
+  let mut input = String::new();
+  if io::stdin().read_line(&mut input).is_err() {
+    return false;
+  }
+  input.trim().eq_ignore_ascii_case("yes")
+}
+
+pub fn display_test_results(cmd: &str, corrections: &[Correction]) {
+  decor!("{}", "Input:".bright_cyan());
+  decor!("  {}", cmd.bright_yellow());
+  decor!();
+
+  if corrections.is_empty() {
+    out!("{}", "No suggestions.".yellow());
+    return;
+  }
+
+  for (i, correction) in corrections.iter().enumerate() {
+    out!(
+      "{} {} {} {}{}",
+      format!("[{}]", i + 1).bright_cyan(),
       correction.fixed_cmd.bright_white().bold(),
-      format!("({})", correction.reason).dimmed()
+      format!("{:.0}%", correction.confidence * 100.0).bright_green(),
+      format!("({})", correction.reason).dimmed(),
+      dropped_badge(cmd, &correction.fixed_cmd)
     );
   }
-  println!();
+  decor!();
+  decor!("{}", "Nothing was executed.".dimmed());
 }
 
 pub fn display_no_suggestions(last_cmd: &str) {
-  println!(
+  out!(
     "{} No suggestions found for: {}",
-    "¯\\_(ツ)_/¯".bright_yellow(),
+    symbols::shrug().bright_yellow(),
     last_cmd.bright_white()
   );
-  println!(
+  if is_quiet() {
+    return;
+  }
+
+  out!(
     "{}",
     "The command might be correct or too complex to fix automatically.".dimmed()
   );
-  println!();
-  println!("{}", "💡 Tip: Add your own fix with:".bright_cyan());
-  println!(
+  out!();
+  out!(
+    "{}",
+    format!("{} Tip: Add your own fix with:", symbols::tip()).bright_cyan()
+  );
+  out!(
     "  {} \"{}\" \"<correct_command>\"",
     "wtf --add".bright_white(),
     last_cmd.dimmed()
   );
+
+  if let Some(hint) = docs_hint(last_cmd) {
+    out!();
+    out!(
+      "{} {}",
+      format!("{} Docs:", symbols::docs()).bright_cyan(),
+      hint.bright_white()
+    );
+  }
+}
+
+// Points at the docs for the command when it exists, i.e. it probably ran but was used wrong.
+fn docs_hint(last_cmd: &str) -> Option<String> {
+  let program = last_cmd.split_whitespace().next()?;
+  if !command_exists(program) {
+    return None;
+  }
+
+  let mut hints = Vec::new();
+  if !cfg!(target_os = "windows") && command_exists("man") {
+    hints.push(format!("man {}", program));
+  } else {
+    hints.push(format!("{} --help", program));
+  }
+  if command_exists("tldr") {
+    hints.push(format!("tldr {}", program));
+  }
+
+  Some(format!("Try: {}", hints.join(" or ")))
 }
 
 pub fn prompt_selection(max: usize) -> Option<usize> {
-  print!(
-    "{} [1-{}] (or 'n' to cancel): ",
-    "Select a fix".bright_cyan(),
-    max
-  );
-  io::stdout().flush().unwrap();
+  prompt_choice("Select a fix", max)
+}
+
+pub fn prompt_choice(label: &str, max: usize) -> Option<usize> {
+  out_inline!("{} [1-{}] (or 'n' to cancel): ", label.bright_cyan(), max);
 
   let mut input = String::new();
   io::stdin().read_line(&mut input).ok()?;
@@ -68,63 +282,355 @@ pub fn prompt_selection(max: usize) -> Option<usize> {
   None
 }
 
+pub fn prompt_yes_no(question: &str, default_yes: bool) -> bool {
+  let hint = if default_yes { "[Y/n]" } else { "[y/N]" };
+  out_inline!("{} {}: ", question.bright_cyan(), hint);
+
+  let mut input = String::new();
+  if io::stdin().read_line(&mut input).is_err() {
+    return false;
+  }
+
+  match input.trim().to_lowercase().as_str() {
+    "" => default_yes,
+    "y" | "yes" => true,
+    _ => false,
+  }
+}
+
+/// The answer at the AI run prompt.
+pub enum RunAnswer {
+  Run,
+  /// Ask the model again with this clarification
+  Refine(String),
+  Cancel,
+}
+
+pub fn prompt_run_or_refine() -> RunAnswer {
+  out_inline!("{} [Y/n/r to refine]: ", "Run this command?".bright_cyan());
+
+  let mut input = String::new();
+  if io::stdin().read_line(&mut input).is_err() {
+    return RunAnswer::Cancel;
+  }
+
+  match input.trim().to_lowercase().as_str() {
+    "" | "y" | "yes" => RunAnswer::Run,
+    "r" | "refine" => {
+      out_inline!("{} ", "What should change?".bright_cyan());
+      let mut hint = String::new();
+      if io::stdin().read_line(&mut hint).is_err() || hint.trim().is_empty() {
+        return RunAnswer::Cancel;
+      }
+      RunAnswer::Refine(hint.trim().to_string())
+    }
+    _ => RunAnswer::Cancel,
+  }
+}
+
+pub fn display_retried_typos(candidates: &[(String, String, usize)], scanned: usize) {
+  if candidates.is_empty() {
+    out!(
+      "{}",
+      format!("No retried typos found in the last {} commands.", scanned).yellow()
+    );
+    return;
+  }
+
+  decor!("{}", "Typos you fixed by hand:".bright_cyan().bold());
+  decor!();
+  for (wrong, correct, count) in candidates {
+    let times = if *count == 1 {
+      String::new()
+    } else {
+      format!(" ({} times)", count)
+    };
+    out!(
+      "  {} {} {}{}",
+      wrong.bright_yellow(),
+      symbols::arrow(),
+      correct.bright_white(),
+      times.dimmed()
+    );
+  }
+  decor!();
+}
+
+pub fn display_recent_commands(entries: &[HistoryEntry], show_source: bool) {
+  decor!("{}", "Recent commands:".bright_cyan().bold());
+  decor!();
+
+  for (i, entry) in entries.iter().enumerate() {
+    let source = if show_source {
+      format!(" ({})", entry.source.name())
+    } else {
+      String::new()
+    };
+
+    out!(
+      "{} {}{}",
+      format!("[{}]", i + 1).bright_cyan(),
+      entry.command.bright_yellow(),
+      source.dimmed()
+    );
+  }
+  decor!();
+}
+
 pub fn display_success(cmd: &str) {
-  println!(
+  decor!(
     "{} {}",
     "Running:".bright_green().bold(),
     cmd.bright_white()
   );
-  println!();
+  decor!();
+}
+
+/// Always on stderr, so piping the fixed command's output stays clean.
+pub fn display_run_result(code: i32) {
+  if code == 0 {
+    if is_quiet() {
+      return;
+    }
+    eprintln!(
+      "{} {}",
+      symbols::ok().bright_green(),
+      "command succeeded".dimmed()
+    );
+  } else {
+    eprintln!(
+      "{} {}",
+      symbols::fail().bright_red(),
+      format!("command exited with code {}", code).bright_red()
+    );
+  }
+}
+
+/// `failed` holds each fix that was tried, with its exit code (None if it never ran).
+pub fn display_run_all_summary(failed: &[(String, Option<i32>)], succeeded: Option<&str>) {
+  decor!();
+
+  for (cmd, code) in failed {
+    let status = match code {
+      Some(code) => format!("exited with code {}", code),
+      None => "not run".to_string(),
+    };
+    out!(
+      "{} {} {}",
+      symbols::fail().bright_red(),
+      cmd.bright_white(),
+      status.dimmed()
+    );
+  }
+
+  match succeeded {
+    Some(cmd) => out!(
+      "{} {} {}",
+      symbols::ok().bright_green(),
+      cmd.bright_white(),
+      "succeeded".dimmed()
+    ),
+    None => out!("{}", "None of the fixes succeeded.".yellow()),
+  }
+}
+
+pub fn display_not_allowed(cmd: &str) {
+  out!("{} {}", "Fix:".bright_green().bold(), cmd.bright_white());
+  decor!();
+  out!(
+    "{}",
+    "Not running: this command is not on your exec_allowlist.".yellow()
+  );
+  decor!(
+    "{}",
+    "Copy the fix above to run it yourself, or add it to exec_allowlist in your config.".dimmed()
+  );
+}
+
+pub fn display_auto_skipped(top: f64, threshold: f64) {
+  out!(
+    "{}",
+    format!(
+      "{} Auto-mode skipped: no high-confidence fix (top fix was {:.0}%, need {:.0}%)",
+      symbols::warn(),
+      top * 100.0,
+      threshold * 100.0
+    )
+    .yellow()
+  );
+  decor!("{}", "Run 'wtf -y' to run it anyway.".dimmed());
+}
+
+pub fn display_cancelled() {
+  out!("{}", "Cancelled.".yellow());
+}
+
+pub fn display_debug(msg: &str) {
+  out!("{}", msg);
 }
 
 pub fn display_error(msg: &str) {
   eprintln!("{} {}", "Error:".bright_red(), msg);
 }
 
-pub fn display_custom_typos(typos: &[(String, String)]) {
+pub fn display_custom_typos(typos: &[CustomTypo]) {
   if typos.is_empty() {
-    println!("{}", "No custom typos configured.".yellow());
-    println!();
-    println!("{}", "Add one with:".dimmed());
-    println!("  wtf --add \"wrong_cmd\" \"correct_cmd\"");
+    out!("{}", "No custom typos configured.".yellow());
+    out!();
+    out!("{}", "Add one with:".dimmed());
+    out!("  wtf --add \"wrong_cmd\" \"correct_cmd\"");
     return;
   }
 
-  println!("{}", "Custom Typos:".bright_cyan().bold());
-  println!();
+  out!("{}", "Custom Typos:".bright_cyan().bold());
+  out!();
 
-  for (i, (wrong, correct)) in typos.iter().enumerate() {
-    println!(
-      "{} {} {} {}",
+  for (i, typo) in typos.iter().enumerate() {
+    let mut flags = Vec::new();
+    if typo.match_mode == MatchMode::Word {
+      flags.push("word");
+    }
+    if typo.ignore_case {
+      flags.push("any case");
+    }
+    let mode = if flags.is_empty() {
+      String::new()
+    } else {
+      format!(" {}", format!("({})", flags.join(", ")).dimmed())
+    };
+    let category = match &typo.category {
+      Some(category) => format!(" {}", format!("[{}]", category).bright_cyan()),
+      None => String::new(),
+    };
+
+    out!(
+      "{} {} {} {}{}{}",
       format!("[{}]", i + 1).bright_black(),
+      typo.wrong.bright_yellow(),
+      symbols::arrow().bright_white(),
+      typo.correct.bright_green(),
+      mode,
+      category
+    );
+  }
+  out!();
+  out!("{} custom typo(s)", typos.len());
+}
+
+pub fn display_builtin_fixes(fixes: &[BuiltinFix], filter: Option<&str>) {
+  if fixes.is_empty() {
+    match filter {
+      Some(term) => out!("{} '{}'", "No built-in fixes match".yellow(), term),
+      None => out!("{}", "No built-in fixes available.".yellow()),
+    }
+    return;
+  }
+
+  out!("{}", "Built-in Fixes:".bright_cyan().bold());
+  out!();
+
+  for (wrong, (correct, reason, confidence)) in fixes {
+    let confidence = match confidence {
+      Some(c) => format!(" {:.0}%", c * 100.0),
+      None => String::new(),
+    };
+
+    out!(
+      "  {} {} {} {}{}",
       wrong.bright_yellow(),
-      "→".bright_white(),
-      correct.bright_green()
+      symbols::arrow().bright_white(),
+      correct.bright_green(),
+      format!("({})", reason).dimmed(),
+      confidence.dimmed()
+    );
+  }
+  out!();
+  out!("{} built-in fix(es)", fixes.len());
+}
+
+pub fn display_typo_issues(typos: &[CustomTypo], issues: &[(usize, TypoIssue)]) {
+  if issues.is_empty() {
+    out!(
+      "{} {} custom typo(s) checked, no problems found",
+      symbols::ok().bright_green(),
+      typos.len()
     );
+    return;
+  }
+
+  out!("{}", "Custom Typo Problems:".bright_cyan().bold());
+  out!();
+
+  for (i, issue) in issues {
+    let typo = &typos[*i];
+    let problem = match issue {
+      TypoIssue::Redundant => "redundant, same as the built-in fix".to_string(),
+      TypoIssue::Conflicts(builtin) => format!("conflicts with built-in fix '{}'", builtin),
+      TypoIssue::Shadowed(first) => format!("never used, [{}] has the same typo", first + 1),
+      TypoIssue::NoOp => "never used, the fix is the typo itself".to_string(),
+      TypoIssue::Inactive => "never used, its category isn't in active_categories".to_string(),
+    };
+
+    out!(
+      "{} {} {} {} {}",
+      format!("[{}]", i + 1).bright_black(),
+      typo.wrong.bright_yellow(),
+      symbols::arrow().bright_white(),
+      typo.correct.bright_green(),
+      format!("({})", problem).dimmed()
+    );
+  }
+  out!();
+}
+
+pub fn display_config_check(path: &str, checks: &[ConfigCheck]) {
+  out!("{} {}", "Checking".bright_cyan(), path.bright_white());
+  out!();
+  out!("{} {}", symbols::ok().bright_green(), "JSON syntax");
+
+  for check in checks {
+    if check.problems.is_empty() {
+      out!("{} {}", symbols::ok().bright_green(), check.name);
+      continue;
+    }
+    out!(
+      "{} {}",
+      symbols::fail().bright_red(),
+      check.name.bright_red()
+    );
+    for problem in &check.problems {
+      out!("    {}", problem.yellow());
+    }
+  }
+
+  let failed = checks.iter().filter(|c| !c.problems.is_empty()).count();
+  out!();
+  if failed == 0 {
+    out!("{}", "Config is valid".bright_green());
+  } else {
+    out!("{}", format!("{} check(s) failed", failed).bright_red());
   }
-  println!();
-  println!("{} custom typo(s)", typos.len());
 }
 
 pub fn display_added(wrong: &str, correct: &str) {
-  println!(
+  out!(
     "{} {} {} {}",
-    "✓".bright_green(),
+    symbols::ok().bright_green(),
     "Added:".bright_green(),
     wrong.bright_yellow(),
-    format!("→ {}", correct).bright_white()
+    format!("{} {}", symbols::arrow(), correct).bright_white()
   );
 }
 
 pub fn display_removed(wrong: &str) {
-  println!(
+  out!(
     "{} {} {}",
-    "✓".bright_green(),
+    symbols::ok().bright_green(),
     "Removed:".bright_green(),
     wrong.bright_yellow()
   );
 }
 
 pub fn display_info(msg: &str) {
-  println!("{}", msg.bright_cyan());
+  out!("{}", msg.bright_cyan());
 }
@@ -0,0 +1,262 @@
+// `wtf self-update`: replaces the binary in ~/.wtf/bin with the latest GitHub release.
+
+use crate::path::{compare_versions, get_install_dir, installed_version};
+use crate::symbols;
+use crate::ui::{display_cancelled, prompt_yes_no};
+use colored::Colorize;
+use reqwest::Client;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/mewisme/wtf/releases/latest";
+
+#[derive(Deserialize)]
+struct Release {
+  tag_name: String,
+  assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+  name: String,
+  browser_download_url: String,
+  size: u64,
+  /// "sha256:<hex>", on releases published since GitHub started recording it
+  digest: Option<String>,
+}
+
+// Asset names as published by the release workflow.
+fn asset_name() -> Option<&'static str> {
+  match (std::env::consts::OS, std::env::consts::ARCH) {
+    ("linux", "x86_64") => Some("wtf-linux-x86_64"),
+    ("macos", "x86_64") => Some("wtf-macos-x86_64"),
+    ("macos", "aarch64") => Some("wtf-macos-aarch64"),
+    ("windows", "x86_64") => Some("wtf-win-x86_64.exe"),
+    ("windows", "x86") => Some("wtf-win-x86.exe"),
+    ("windows", "aarch64") => Some("wtf-win-aarch64.exe"),
+    _ => None,
+  }
+}
+
+fn installed_binary() -> Result<PathBuf, String> {
+  let binary_name = if cfg!(target_os = "windows") {
+    "wtf.exe"
+  } else {
+    "wtf"
+  };
+  let binary = get_install_dir()?.join(binary_name);
+
+  // A copy from a package manager or `cargo install` is theirs to update
+  if !binary.exists() {
+    return Err(format!(
+      "wtf is not installed in {}. Run 'wtf install' first, or update it the way you installed it.",
+      binary.parent().unwrap_or(&binary).display()
+    ));
+  }
+  Ok(binary)
+}
+
+pub async fn self_update(yes: bool) -> Result<(), String> {
+  let binary = installed_binary()?;
+  let asset_name = asset_name().ok_or_else(|| {
+    format!(
+      "No release binary for {} {}; build from source instead",
+      std::env::consts::OS,
+      std::env::consts::ARCH
+    )
+  })?;
+
+  let client = Client::new();
+  let release: Release = client
+    .get(LATEST_RELEASE_URL)
+    .header("User-Agent", concat!("wtf/", env!("CARGO_PKG_VERSION")))
+    .header("Accept", "application/vnd.github+json")
+    .send()
+    .await
+    .and_then(|response| response.error_for_status())
+    .map_err(|e| format!("Failed to check the latest release: {}", e))?
+    .json()
+    .await
+    .map_err(|e| format!("Failed to read the latest release: {}", e))?;
+
+  let latest = release.tag_name.trim_start_matches('v');
+  let current = installed_version(&binary).unwrap_or_else(|| "unknown".to_string());
+  println!("  {} {}", "Installed:".bright_cyan(), current);
+  println!("  {} {}", "Latest:".bright_cyan(), latest);
+  println!();
+
+  // An unreadable version compares lowest, so it gets replaced
+  if compare_versions(&current, latest) != Ordering::Less {
+    println!(
+      "{} {}",
+      symbols::ok().bright_green(),
+      "wtf is up to date".bright_green()
+    );
+    return Ok(());
+  }
+
+  let asset = release
+    .assets
+    .iter()
+    .find(|asset| asset.name == asset_name)
+    .ok_or_else(|| format!("Release {} has no {} binary", release.tag_name, asset_name))?;
+
+  let expected = published_checksum(&client, &release, asset).await?;
+  if expected.is_none() {
+    println!(
+      "{}",
+      format!(
+        "{} Release {} publishes no checksum for {}, so the download can't be verified.",
+        symbols::warn(),
+        release.tag_name,
+        asset.name
+      )
+      .yellow()
+    );
+    if !yes && !prompt_yes_no("Install it unverified?", false) {
+      display_cancelled();
+      return Ok(());
+    }
+  }
+
+  println!("{} Downloading {}...", symbols::info(), asset.name);
+  let bytes = client
+    .get(&asset.browser_download_url)
+    .header("User-Agent", concat!("wtf/", env!("CARGO_PKG_VERSION")))
+    .send()
+    .await
+    .and_then(|response| response.error_for_status())
+    .map_err(|e| format!("Failed to download {}: {}", asset.name, e))?
+    .bytes()
+    .await
+    .map_err(|e| format!("Failed to download {}: {}", asset.name, e))?;
+
+  verify_download(asset, &bytes, expected.as_deref())?;
+  replace_binary(&binary, &bytes, latest)?;
+
+  println!(
+    "{} {}",
+    symbols::ok().bright_green(),
+    format!("Updated wtf {} {} {}", current, symbols::arrow(), latest).bright_green()
+  );
+  Ok(())
+}
+
+// The SHA-256 GitHub records for the asset, or else the one in a `<asset>.sha256` file
+// published next to it. None when the release has neither.
+async fn published_checksum(
+  client: &Client,
+  release: &Release,
+  asset: &Asset,
+) -> Result<Option<String>, String> {
+  if let Some(digest) = asset
+    .digest
+    .as_deref()
+    .and_then(|digest| digest.strip_prefix("sha256:"))
+  {
+    return Ok(Some(digest.to_string()));
+  }
+
+  let checksum_name = format!("{}.sha256", asset.name);
+  let Some(checksum_asset) = release.assets.iter().find(|a| a.name == checksum_name) else {
+    return Ok(None);
+  };
+
+  // `sha256sum` output: the hex digest, then the file name
+  let content = client
+    .get(&checksum_asset.browser_download_url)
+    .header("User-Agent", concat!("wtf/", env!("CARGO_PKG_VERSION")))
+    .send()
+    .await
+    .and_then(|response| response.error_for_status())
+    .map_err(|e| format!("Failed to download {}: {}", checksum_name, e))?
+    .text()
+    .await
+    .map_err(|e| format!("Failed to download {}: {}", checksum_name, e))?;
+
+  match content.split_whitespace().next() {
+    Some(hex) if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) => {
+      Ok(Some(hex.to_string()))
+    }
+    _ => Err(format!(
+      "{} doesn't contain a SHA-256 checksum",
+      checksum_name
+    )),
+  }
+}
+
+fn verify_download(asset: &Asset, bytes: &[u8], expected: Option<&str>) -> Result<(), String> {
+  if bytes.len() as u64 != asset.size {
+    return Err(format!(
+      "Download is {} bytes, expected {}; try again",
+      bytes.len(),
+      asset.size
+    ));
+  }
+
+  let Some(expected) = expected else {
+    return Ok(());
+  };
+
+  let actual: String = Sha256::digest(bytes)
+    .iter()
+    .map(|byte| format!("{:02x}", byte))
+    .collect();
+  if !actual.eq_ignore_ascii_case(expected) {
+    return Err(format!(
+      "Checksum mismatch for {} (expected {}, got {}); not installing it",
+      asset.name, expected, actual
+    ));
+  }
+  Ok(())
+}
+
+// Stages the new binary next to the old one, checks it runs and reports the expected
+// version, then renames it into place so no half-written binary is ever installed.
+fn replace_binary(binary: &Path, bytes: &[u8], version: &str) -> Result<(), String> {
+  // Windows only runs it with its .exe extension
+  let staged = if cfg!(target_os = "windows") {
+    binary.with_extension("new.exe")
+  } else {
+    binary.with_extension("new")
+  };
+  fs::write(&staged, bytes).map_err(|e| format!("Failed to write {}: {}", staged.display(), e))?;
+
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))
+      .map_err(|e| format!("Failed to make {} executable: {}", staged.display(), e))?;
+  }
+
+  if installed_version(&staged).as_deref() != Some(version) {
+    let _ = fs::remove_file(&staged);
+    return Err(format!(
+      "The downloaded binary doesn't run or doesn't report version {}; not installing it",
+      version
+    ));
+  }
+
+  swap_into_place(&staged, binary)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn swap_into_place(staged: &Path, binary: &Path) -> Result<(), String> {
+  fs::rename(staged, binary).map_err(|e| format!("Failed to replace {}: {}", binary.display(), e))
+}
+
+// A running .exe can't be overwritten, but it can be renamed out of the way.
+#[cfg(target_os = "windows")]
+fn swap_into_place(staged: &Path, binary: &Path) -> Result<(), String> {
+  let old = binary.with_extension("old");
+  let _ = fs::remove_file(&old);
+  fs::rename(binary, &old)
+    .map_err(|e| format!("Failed to move {} aside: {}", binary.display(), e))?;
+  fs::rename(staged, binary).map_err(|e| {
+    let _ = fs::rename(&old, binary);
+    format!("Failed to replace {}: {}", binary.display(), e)
+  })
+}